@@ -0,0 +1,74 @@
+// crossplatform_path/src/case_insensitive.rs
+
+//! Case-insensitive comparison of paths with Unicode case folding.
+//!
+//! Windows and the default macOS volumes are case-insensitive, so `/mnt/c/Users/Foo` and `/mnt/c/users/foo` are the same location.
+//! The derived comparison of CrossPathBuf is case-sensitive like Linux. The CaseInsensitive wrapper compares the case-folded paths.
+
+use crate::CrossPath;
+
+/// Returns the path with the Unicode default case folding, so `Straße` and `STRASSE` are equal.
+pub(crate) fn case_fold(cross_path: &CrossPath) -> String {
+    caseless::default_case_fold_str(cross_path.as_str())
+}
+
+/// How the "same source and destination" check compares the paths in copy_file_to_file_with() and rename_or_move_with().
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CaseSensitivity {
+    /// The paths are compared exactly, like on Linux. This is the default.
+    #[default]
+    Sensitive,
+    /// If both paths are on a Windows drive `/mnt/c`, they are compared case-insensitive, because Windows drives are case-insensitive.
+    IgnoreCaseOnDrives,
+}
+
+/// Wrapper that compares, hashes and orders the path case-insensitive with Unicode case folding.  \
+///
+/// It can be used as a key in HashMap or BTreeMap, when the paths come from a case-insensitive filesystem:
+///
+/// ```rust
+/// use crossplatform_path::{CaseInsensitive, CrossPathBuf};
+///
+/// let mut set = std::collections::HashSet::new();
+/// set.insert(CaseInsensitive(CrossPathBuf::new(r#"C:\Users\Foo"#)?));
+/// assert!(set.contains(&CaseInsensitive(CrossPathBuf::new("/mnt/c/users/foo")?)));
+/// # Ok::<(), crossplatform_path::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct CaseInsensitive<T>(pub T);
+
+/// Equal if the case-folded paths are equal.
+impl<T: AsRef<CrossPath>> PartialEq for CaseInsensitive<T> {
+    /// Equal if the case-folded paths are equal.
+    fn eq(&self, other: &Self) -> bool {
+        case_fold(self.0.as_ref()) == case_fold(other.0.as_ref())
+    }
+}
+
+impl<T: AsRef<CrossPath>> Eq for CaseInsensitive<T> {}
+
+/// Hash is on the case-folded path, so it is consistent with Eq.
+impl<T: AsRef<CrossPath>> std::hash::Hash for CaseInsensitive<T> {
+    /// Hash is on the case-folded path, so it is consistent with Eq.
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        case_fold(self.0.as_ref()).hash(state);
+    }
+}
+
+/// Ordering is the component-wise ordering of the case-folded paths.
+impl<T: AsRef<CrossPath>> Ord for CaseInsensitive<T> {
+    /// Ordering is the component-wise ordering of the case-folded paths.
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let self_folded = case_fold(self.0.as_ref());
+        let other_folded = case_fold(other.0.as_ref());
+        CrossPath::from_valid_str(&self_folded).cmp(CrossPath::from_valid_str(&other_folded))
+    }
+}
+
+/// Ordering is the component-wise ordering of the case-folded paths.
+impl<T: AsRef<CrossPath>> PartialOrd for CaseInsensitive<T> {
+    /// Ordering is the component-wise ordering of the case-folded paths.
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
@@ -0,0 +1,250 @@
+// crossplatform_path/src/components.rs
+
+//! Iterators over the components of a neutral crossplatform path.
+//!
+//! The neutral path can start with an anchor: the root `/`, the home `~` or `~user`, the Windows drive `/mnt/c`, the temp folder `/tmp`
+//! or the UNC network share `//server/share`.
+//! The anchor is recognized only as whole leading component(s). After the anchor come the normal names.
+
+use crate::CrossPath;
+
+/// One component of a path in the Neutral Crossplatform format.  \
+///
+/// Only the first component can be an anchor: Root, Home, UserHome, Drive, Temp or Unc.  \
+/// The anchors have a special meaning and are transformed for the current OS.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CrossComponent<'a> {
+    /// The leading `/` of an absolute path.
+    Root,
+    /// The leading `~` is the home folder of the user.
+    Home,
+    /// The leading `~user` is the home folder of another user. It contains the user name.
+    UserHome(&'a str),
+    /// The leading `/mnt/c` is the Windows drive `c:`. It contains the drive letter.
+    Drive(char),
+    /// The leading `/tmp` is the temp folder.
+    Temp,
+    /// The leading `//server/share` is the Windows UNC network share `\\server\share`.
+    Unc {
+        /// The name of the server.
+        server: &'a str,
+        /// The name of the shared folder.
+        share: &'a str,
+    },
+    /// A normal file or folder name.
+    Normal(&'a str),
+}
+
+/// Recognizes the anchor at the start of the neutral path.  \
+///
+/// Returns the component and the length in bytes of the anchor text.
+fn parse_anchor(cross_path: &str) -> Option<(CrossComponent<'_>, usize)> {
+    /// The anchor text must be followed by a slash or be the whole path.
+    fn is_whole_component(cross_path: &str, anchor: &str) -> bool {
+        cross_path
+            .strip_prefix(anchor)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+    if is_whole_component(cross_path, "~") {
+        return Some((CrossComponent::Home, 1));
+    }
+    if let Some(rest) = cross_path.strip_prefix('~') {
+        let user = rest.split('/').next().unwrap_or("");
+        if is_user_name(user) {
+            return Some((CrossComponent::UserHome(user), "~".len() + user.len()));
+        }
+    }
+    if let Some(rest) = cross_path.strip_prefix("/mnt/")
+        && let Some(letter) = rest.chars().next()
+        && letter.is_ascii_alphabetic()
+        && is_whole_component(rest, &rest[..1])
+    {
+        return Some((CrossComponent::Drive(letter), "/mnt/c".len()));
+    }
+    if is_whole_component(cross_path, "/tmp") {
+        return Some((CrossComponent::Temp, "/tmp".len()));
+    }
+    if let Some(unc_path) = cross_path.strip_prefix("//") {
+        let (server, after_server) = unc_path.split_once('/')?;
+        let share = after_server.split('/').next()?;
+        return Some((CrossComponent::Unc { server, share }, "//".len() + server.len() + 1 + share.len()));
+    }
+    if cross_path.starts_with('/') {
+        return Some((CrossComponent::Root, 1));
+    }
+    None
+}
+
+/// The user name in `~user` uses the POSIX portable characters and does not start with `-` or `.`.  \
+///
+/// Other names that start with `~` like `~$report.docx` stay normal names.
+fn is_user_name(user: &str) -> bool {
+    user.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+        && user.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+/// Returns `true` if the name is the anchor `~` or `~user` when it is the first component.
+pub(crate) fn is_home_anchor(name: &str) -> bool {
+    name.strip_prefix('~').is_some_and(|user| user.is_empty() || is_user_name(user))
+}
+
+/// Iterator over the CrossComponent of a CrossPath.  \
+///
+/// Created with CrossPath::components(). It can be iterated also from the back.
+#[derive(Clone, Debug)]
+pub struct Components<'a> {
+    /// The part of the path that is not yet consumed.
+    path: &'a str,
+    /// The anchor can be only at the start of the path. It is false after the front was consumed.
+    at_start: bool,
+}
+
+impl<'a> Components<'a> {
+    /// Components of the neutral path.
+    pub(crate) fn new(cross_path: &'a CrossPath) -> Self {
+        Components {
+            path: cross_path.as_str(),
+            at_start: true,
+        }
+    }
+
+    /// Returns the remaining part of the path that is not yet consumed.
+    pub fn as_cross_path(&self) -> &'a CrossPath {
+        if self.at_start {
+            CrossPath::from_valid_str(self.path)
+        } else {
+            CrossPath::from_valid_str(self.path.trim_start_matches('/'))
+        }
+    }
+
+    /// Length of the anchor that is not yet consumed.
+    fn anchor_len(&self) -> usize {
+        if self.at_start {
+            parse_anchor(self.path).map_or(0, |(_component, len)| len)
+        } else {
+            0
+        }
+    }
+
+    /// Returns the next component from the front together with its text.
+    fn next_with_text(&mut self) -> Option<(CrossComponent<'a>, &'a str)> {
+        if self.at_start {
+            self.at_start = false;
+            if let Some((component, len)) = parse_anchor(self.path) {
+                let text = &self.path[..len];
+                self.path = &self.path[len..];
+                return Some((component, text));
+            }
+        }
+        let trimmed = self.path.trim_start_matches('/');
+        if trimmed.is_empty() {
+            self.path = "";
+            return None;
+        }
+        let (name, rest) = trimmed.split_once('/').unwrap_or((trimmed, ""));
+        self.path = rest;
+        Some((CrossComponent::Normal(name), name))
+    }
+
+    /// Returns the next component from the back together with its text.
+    fn next_back_with_text(&mut self) -> Option<(CrossComponent<'a>, &'a str)> {
+        let anchor_len = self.anchor_len();
+        let names = self.path[anchor_len..].trim_end_matches('/');
+        if names.trim_start_matches('/').is_empty() {
+            // only the anchor is left
+            let anchor = if anchor_len > 0 { parse_anchor(self.path) } else { None };
+            let text = &self.path[..anchor_len];
+            self.at_start = false;
+            self.path = "";
+            return anchor.map(|(component, _len)| (component, text));
+        }
+        let name = match names.rfind('/') {
+            Some(pos) => {
+                self.path = &self.path[..anchor_len + pos];
+                &names[pos + 1..]
+            }
+            None => {
+                self.path = &self.path[..anchor_len];
+                names
+            }
+        };
+        Some((CrossComponent::Normal(name), name))
+    }
+}
+
+impl<'a> Iterator for Components<'a> {
+    type Item = CrossComponent<'a>;
+
+    fn next(&mut self) -> Option<CrossComponent<'a>> {
+        self.next_with_text().map(|(component, _text)| component)
+    }
+}
+
+impl<'a> DoubleEndedIterator for Components<'a> {
+    fn next_back(&mut self) -> Option<CrossComponent<'a>> {
+        self.next_back_with_text().map(|(component, _text)| component)
+    }
+}
+
+/// Iterator over the components of a CrossPath as str slices.  \
+///
+/// Created with CrossPath::iter(). The anchors are returned as they are written: `/`, `~`, `/mnt/c`, `/tmp` or `//server/share`.
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    /// Iter is just a different view of Components.
+    inner: Components<'a>,
+}
+
+impl<'a> Iter<'a> {
+    /// Iterator over the components as str slices.
+    pub(crate) fn new(cross_path: &'a CrossPath) -> Self {
+        Iter {
+            inner: Components::new(cross_path),
+        }
+    }
+
+    /// Returns the remaining part of the path that is not yet consumed.
+    pub fn as_cross_path(&self) -> &'a CrossPath {
+        self.inner.as_cross_path()
+    }
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.inner.next_with_text().map(|(_component, text)| text)
+    }
+}
+
+impl<'a> DoubleEndedIterator for Iter<'a> {
+    fn next_back(&mut self) -> Option<&'a str> {
+        self.inner.next_back_with_text().map(|(_component, text)| text)
+    }
+}
+
+/// Iterator over the CrossPath and all its ancestors.  \
+///
+/// Created with CrossPath::ancestors(). It returns the path itself, then the parent, then the parent of the parent,...
+#[derive(Clone, Debug)]
+pub struct Ancestors<'a> {
+    /// The next path to return.
+    next: Option<&'a CrossPath>,
+}
+
+impl<'a> Ancestors<'a> {
+    /// Iterator over the path and all its ancestors.
+    pub(crate) fn new(cross_path: &'a CrossPath) -> Self {
+        Ancestors { next: Some(cross_path) }
+    }
+}
+
+impl<'a> Iterator for Ancestors<'a> {
+    type Item = &'a CrossPath;
+
+    fn next(&mut self) -> Option<&'a CrossPath> {
+        let next = self.next?;
+        self.next = next.parent().ok();
+        Some(next)
+    }
+}
@@ -0,0 +1,48 @@
+// crossplatform_path/src/cross_path_serde.rs
+
+//! Serialize and Deserialize for CrossPathBuf behind the cargo feature `serde`.
+//!
+//! The serialized value is always the neutral crossplatform string from as_str().
+//! Deserialization goes through CrossPathBuf::new(), so the same validation is applied as in the code.
+
+use crate::{CrossPath, CrossPathBuf};
+
+/// Serialize writes the neutral crossplatform string.
+impl serde::Serialize for CrossPath {
+    /// Serialize writes the neutral crossplatform string.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Serialize writes the neutral crossplatform string.
+impl serde::Serialize for CrossPathBuf {
+    /// Serialize writes the neutral crossplatform string.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Deserialize validates the string with CrossPathBuf::new().
+impl<'de> serde::Deserialize<'de> for CrossPathBuf {
+    /// Deserialize validates the string with CrossPathBuf::new().
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(CrossPathBufVisitor)
+    }
+}
+
+/// Visitor accepts only strings.
+struct CrossPathBufVisitor;
+
+impl serde::de::Visitor<'_> for CrossPathBufVisitor {
+    type Value = CrossPathBuf;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a string with a path in the neutral crossplatform format")
+    }
+
+    /// The error message of crate::Error already contains the offending value.
+    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<CrossPathBuf, E> {
+        CrossPathBuf::new(value).map_err(E::custom)
+    }
+}
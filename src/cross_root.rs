@@ -0,0 +1,166 @@
+// crossplatform_path/src/cross_root.rs
+
+//! CrossRoot is a capability to access files only inside a root folder.
+//!
+//! Per-tenant data folders must never be left with a path like `../other_tenant` or `/etc/passwd`.
+//! Every path is joined to the root and checked lexically and, optionally, after resolving symlinks.
+
+use crate::{CrossComponent, CrossPath, CrossPathBuf, DotComponents, Error, Result, ValidationProfile};
+
+/// The root folder that all the file operations must stay inside.  \
+///
+/// The paths are relative to the root. The `.` and `..` are resolved lexically,
+/// but a path that climbs above the root or starts with an anchor like `/`, `~` or `/mnt/c` returns Error::EscapesRoot.  \
+/// The native path used by the file operations is checked too, so it must be inside the native root and must not contain `..`.  \
+/// With new_resolving_symlinks() the target is checked also after resolving the symlinks,
+/// so a symlink inside the tree cannot point outside of the root.
+/// The check and the file operation are not atomic, so the tree must not be changed concurrently by an untrusted party.
+///
+/// ```rust
+/// use crossplatform_path::{CrossPathBuf, CrossRoot, Error};
+///
+/// let root = CrossRoot::new(CrossPathBuf::new("/tmp/tenants/tenant_1")?);
+/// assert_eq!(root.join("data/./config.toml")?, "/tmp/tenants/tenant_1/data/config.toml");
+/// assert!(matches!(root.join("../tenant_2/config.toml"), Err(Error::EscapesRoot { .. })));
+/// assert!(matches!(root.join("/etc/passwd"), Err(Error::EscapesRoot { .. })));
+/// # Ok::<(), crossplatform_path::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CrossRoot {
+    /// All the targets must be inside this folder.
+    root: CrossPathBuf,
+    /// The target is checked also after resolving the symlinks.
+    resolve_symlinks: bool,
+}
+
+impl CrossRoot {
+    /// Creates the root with only the lexical check.
+    pub fn new(root: CrossPathBuf) -> Self {
+        CrossRoot {
+            root,
+            resolve_symlinks: false,
+        }
+    }
+
+    /// Creates the root that checks the target also after resolving the symlinks.  \
+    ///
+    /// The root folder must exist when a path is joined.
+    pub fn new_resolving_symlinks(root: CrossPathBuf) -> Self {
+        CrossRoot {
+            root,
+            resolve_symlinks: true,
+        }
+    }
+
+    /// Returns the root folder.
+    pub fn as_cross_path(&self) -> &CrossPath {
+        self.root.as_cross_path()
+    }
+
+    /// Joins the relative path to the root and checks that the target is inside the root.  \
+    ///
+    /// Returns Error::EscapesRoot if the path starts with an anchor, if `..` climbs above the root
+    /// or if a symlink points outside of the root.
+    pub fn join(&self, str_path: &str) -> Result<CrossPathBuf> {
+        let escapes_root = || Error::EscapesRoot {
+            path: str_path.to_string(),
+            root: self.root.to_string(),
+        };
+        let profile = ValidationProfile {
+            dot_components: DotComponents::Resolve,
+            ..ValidationProfile::default()
+        };
+        let relative_path = match CrossPathBuf::new_with_profile(str_path, &profile) {
+            Err(Error::ClimbsAboveStart(_violation)) => return Err(escapes_root()),
+            other => other?,
+        };
+        // an absolute path like /etc or ~/.ssh is not appended silently
+        if !matches!(relative_path.components().next(), None | Some(CrossComponent::Normal(_))) {
+            return Err(escapes_root());
+        }
+        let mut target = self.root.clone();
+        target.push_relative(&relative_path);
+        // the file operations use the native path, where the escapes of an escaped root like %2E%2E are decoded
+        let native_target = target.as_native_path();
+        if native_target
+            .components()
+            .any(|component| component == std::path::Component::ParentDir)
+            || !native_target.starts_with(self.root.as_native_path())
+        {
+            return Err(escapes_root());
+        }
+        if self.resolve_symlinks && !self.is_inside_after_symlinks(&target)? {
+            return Err(escapes_root());
+        }
+        Ok(target)
+    }
+
+    /// Resolves the symlinks of the deepest existing ancestor of the target and compares it with the resolved root.
+    fn is_inside_after_symlinks(&self, target: &CrossPathBuf) -> Result<bool> {
+        let canonical_root = std::fs::canonicalize(self.root.as_native_path()).map_err(|err| Error::IoError {
+            source: (err),
+            path: (self.root.to_string()),
+        })?;
+        let Some(existing) = target
+            .as_native_path()
+            .ancestors()
+            .find(|ancestor| ancestor.symlink_metadata().is_ok())
+        else {
+            return Ok(false);
+        };
+        let canonical_existing = std::fs::canonicalize(existing).map_err(|err| Error::IoError {
+            source: (err),
+            path: (target.to_string()),
+        })?;
+        Ok(canonical_existing.starts_with(&canonical_root))
+    }
+
+    /// Reads the entire contents of a file inside the root into a string.
+    pub fn read_to_string(&self, str_path: &str) -> Result<String> {
+        self.join(str_path)?.read_to_string()
+    }
+
+    /// Writes a string slice as the entire contents of a file inside the root.
+    pub fn write_str_to_file(&self, str_path: &str, content: &str) -> Result<()> {
+        self.join(str_path)?.write_str_to_file(content)
+    }
+
+    /// Writes a byte slice as the entire contents of a file inside the root.
+    pub fn write_bytes_to_file(&self, str_path: &str, content: &[u8]) -> Result<()> {
+        self.join(str_path)?.write_bytes_to_file(content)
+    }
+
+    /// Recursively creates a directory inside the root.
+    pub fn create_dir_all(&self, str_path: &str) -> Result<()> {
+        self.join(str_path)?.create_dir_all()
+    }
+
+    /// Removes a file inside the root.
+    pub fn remove_file(&self, str_path: &str) -> Result<()> {
+        self.join(str_path)?.remove_file()
+    }
+
+    /// Removes a directory inside the root, after removing all its contents.  \
+    ///
+    /// The root itself cannot be removed, because an empty path returns Error::EscapesRoot.
+    pub fn remove_dir_all(&self, str_path: &str) -> Result<()> {
+        let target = self.join(str_path)?;
+        if target == self.root {
+            return Err(Error::EscapesRoot {
+                path: str_path.to_string(),
+                root: self.root.to_string(),
+            });
+        }
+        target.remove_dir_all()
+    }
+
+    /// Copies a file inside the root to another file inside the root.
+    pub fn copy_file_to_file(&self, source: &str, destination: &str) -> Result<()> {
+        self.join(source)?.copy_file_to_file(&self.join(destination)?)
+    }
+
+    /// Renames a file or directory inside the root to another name inside the root.
+    pub fn rename_or_move(&self, source: &str, destination: &str) -> Result<()> {
+        self.join(source)?.rename_or_move(&self.join(destination)?)
+    }
+}
@@ -0,0 +1,73 @@
+// crossplatform_path/src/env_expand.rs
+
+//! Expansion of environment variables inside a path string.
+//!
+//! It understands the POSIX syntax `$VAR` and `${VAR}` and the Windows syntax `%VAR%`.
+//! The values are looked up with a function, so tests don't depend on the real environment.
+
+use crate::{Error, Result};
+
+/// Replaces all the variables in the text with values from the lookup function.  \
+///
+/// A `$` or `%` that does not start a valid variable name stays as it is.  \
+/// With skip_escapes the `%XX` escapes of an escaped path stay as they are and are not the start of `%VAR%`.  \
+/// If the lookup returns None, it returns the Error::EnvVarNotSet with the variable name.
+pub(crate) fn expand_variables(text: &str, lookup: &dyn Fn(&str) -> Option<String>, skip_escapes: bool) -> Result<String> {
+    let mut expanded = String::new();
+    let mut rest = text;
+    while let Some(pos) = rest.find(['$', '%']) {
+        expanded.push_str(&rest[..pos]);
+        let sigil = &rest[pos..pos + 1];
+        let after = &rest[pos + 1..];
+        if skip_escapes && crate::escape::is_escape_sequence(&rest.as_bytes()[pos..]) {
+            expanded.push_str(&rest[pos..pos + 3]);
+            rest = &rest[pos + 3..];
+            continue;
+        }
+        let variable = if sigil == "$" {
+            split_posix_variable(after)
+        } else {
+            split_windows_variable(after)
+        };
+        let Some((name, remaining)) = variable else {
+            // not a variable, just an ordinary character
+            expanded.push_str(sigil);
+            rest = after;
+            continue;
+        };
+        let value = lookup(name).ok_or_else(|| Error::EnvVarNotSet(name.to_string()))?;
+        expanded.push_str(&value);
+        rest = remaining;
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Splits `VAR...` or `{VAR}...` after the `$` into the name and the remaining text.
+fn split_posix_variable(after: &str) -> Option<(&str, &str)> {
+    if let Some(braced) = after.strip_prefix('{') {
+        let end = braced.find('}')?;
+        let name = &braced[..end];
+        return is_posix_name(name).then_some((name, &braced[end + 1..]));
+    }
+    let len = after
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(after.len());
+    let name = &after[..len];
+    is_posix_name(name).then_some((name, &after[len..]))
+}
+
+/// Splits `VAR%...` after the first `%` into the name and the remaining text.  \
+///
+/// Windows names can contain parenthesis like `%ProgramFiles(x86)%`.
+fn split_windows_variable(after: &str) -> Option<(&str, &str)> {
+    let end = after.find('%')?;
+    let name = &after[..end];
+    let is_windows_name = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '(' | ')'));
+    is_windows_name.then_some((name, &after[end + 1..]))
+}
+
+/// POSIX names are letters, digits and underscore, but must not start with a digit.
+fn is_posix_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
@@ -0,0 +1,183 @@
+// crossplatform_path/src/escape.rs
+
+//! Reversible percent escaping for names that the neutral format cannot represent.
+//!
+//! Linux allows names like `report: Q1?.txt`, `con` or bytes that are not utf8.
+//! CrossPathBuf::from_path_escaped() writes the offending bytes as `%3A` with uppercase hex digits and marks the path as escaped.
+//! Only for the escaped paths the conversion to the native path decodes the `%XX` back into the original byte.
+//! A literal `%` followed by two uppercase hex digits is escaped as `%25`, so the round-trip is byte-identical.
+//! The separators `%2F` and, for Windows, `%5C` and the NULL byte `%00` are never decoded, so a name cannot become a path.
+
+use crate::TargetOs;
+
+/// Escapes the native path into a string that passes the validation of CrossPathBuf::new().
+pub(crate) fn escape_native_path(path: &std::path::Path) -> String {
+    let bytes = strip_extended_length_prefix(path.as_os_str().as_encoded_bytes());
+    // On Windows the backslash is a separator, on Linux it is a valid character in a name.
+    let is_separator = |byte: &u8| *byte == b'/' || (cfg!(windows) && *byte == b'\\');
+    let leading_separators = bytes.iter().take_while(|byte| is_separator(byte)).count();
+    let mut escaped = match leading_separators {
+        0 => String::new(),
+        // UNC network path \\server\share
+        2.. if cfg!(windows) => "//".to_string(),
+        _ => "/".to_string(),
+    };
+    let is_absolute = leading_separators > 0;
+    let names = bytes[leading_separators..].split(is_separator).filter(|name| !name.is_empty());
+    for (index, name) in names.enumerate() {
+        if index > 0 {
+            escaped.push('/');
+        }
+        escaped.push_str(&escape_name(name, index == 0, is_absolute));
+    }
+    // the trailing slash of a folder is preserved
+    if bytes.len() > leading_separators && bytes.last().is_some_and(is_separator) {
+        escaped.push('/');
+    }
+    escaped
+}
+
+/// The extended-length prefix `\\?\` is recognized only on Windows. On Linux it is a strange, but valid name.
+fn strip_extended_length_prefix(bytes: &[u8]) -> std::borrow::Cow<'_, [u8]> {
+    if cfg!(windows)
+        && let Some(rest) = bytes.strip_prefix(br"\\?\")
+    {
+        if let Some(unc_path) = rest.strip_prefix(br"UNC\") {
+            return std::borrow::Cow::Owned([br"\\", unc_path].concat());
+        }
+        return std::borrow::Cow::Borrowed(rest);
+    }
+    std::borrow::Cow::Borrowed(bytes)
+}
+
+/// Escapes one file or folder name.  \
+///
+/// The first name of a path can be mistaken for an anchor, so it has some additional rules.
+fn escape_name(name: &[u8], is_first: bool, is_absolute: bool) -> String {
+    let mut escaped = String::new();
+    let mut offset = 0;
+    for chunk in name.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            // the Windows drive letter c: is kept, so it becomes /mnt/c
+            let is_drive_colon = cfg!(windows) && is_first && !is_absolute && offset == 1 && name[0].is_ascii_alphabetic();
+            let is_forbidden = matches!(c, '<' | '>' | '"' | '|' | '?' | '*' | '\\' | '\u{0}'..='\u{1F}' | '\u{7F}')
+                || (c == ':' && !is_drive_colon)
+                || (c == '%' && is_escape_sequence(&name[offset..]))
+                || (is_first && !is_absolute && offset == 0 && (c.is_whitespace() || c == '~'));
+            if is_forbidden {
+                push_escaped_char(&mut escaped, c);
+            } else {
+                escaped.push(c);
+            }
+            offset += c.len_utf8();
+        }
+        for byte in chunk.invalid() {
+            push_escaped_byte(&mut escaped, *byte);
+            offset += 1;
+        }
+    }
+    // . and .. are not allowed as names
+    if escaped == "." || escaped == ".." {
+        escaped = escaped.replace('.', "%2E");
+    }
+    // Windows cannot create a name that ends with space or dot.
+    if let Some(last) = escaped.chars().last()
+        && (last == ' ' || last == '.')
+    {
+        escaped.pop();
+        push_escaped_char(&mut escaped, last);
+    }
+    // The first character of the reserved device names is always an ASCII letter: con, prn, aux, nul, com1, lpt1.
+    // The Cygwin drive `/cygdrive` must stay a normal name. The leading `~` is already escaped.
+    let is_anchor_like = is_first && is_absolute && escaped == "cygdrive";
+    if is_anchor_like || crate::validation::is_reserved_device_name(&escaped) {
+        let first = escaped.remove(0);
+        let mut first_escaped = String::new();
+        push_escaped_char(&mut first_escaped, first);
+        escaped.insert_str(0, &first_escaped);
+    }
+    escaped
+}
+
+/// A `%` followed by two uppercase hex digits is decoded in escaped paths, so a literal one must be escaped.
+pub(crate) fn is_escape_sequence(bytes: &[u8]) -> bool {
+    bytes.len() >= 3 && bytes[0] == b'%' && is_upper_hex(bytes[1]) && is_upper_hex(bytes[2])
+}
+
+/// Only uppercase hex digits are decoded, like the escapes are written.
+fn is_upper_hex(byte: u8) -> bool {
+    byte.is_ascii_digit() || (b'A'..=b'F').contains(&byte)
+}
+
+/// Writes all the utf8 bytes of the char as escapes.
+fn push_escaped_char(escaped: &mut String, c: char) {
+    for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+        push_escaped_byte(escaped, byte);
+    }
+}
+
+/// Writes one byte as `%XX` with uppercase hex digits.
+fn push_escaped_byte(escaped: &mut String, byte: u8) {
+    escaped.push_str(&format!("%{byte:02X}"));
+}
+
+/// Decodes the escapes in the names and creates the native path.  \
+///
+/// The prefix comes from the context like the home folder and it is not decoded.
+pub(crate) fn unescape_to_path_buf(prefix: &str, names: &str, target_os: TargetOs) -> std::path::PathBuf {
+    if !names.contains('%') {
+        return std::path::PathBuf::from(format!("{prefix}{names}"));
+    }
+    let mut bytes = prefix.as_bytes().to_vec();
+    bytes.extend(unescape_bytes(names, target_os));
+    path_buf_from_bytes(bytes)
+}
+
+/// Decodes the escapes in the names into a string. The bytes that are not utf8 are replaced.
+pub(crate) fn unescape_to_string_lossy(names: &str, target_os: TargetOs) -> String {
+    if !names.contains('%') {
+        return names.to_string();
+    }
+    String::from_utf8_lossy(&unescape_bytes(names, target_os)).to_string()
+}
+
+/// Decodes every `%XX` with uppercase hex digits, except the separators and the NULL byte.
+fn unescape_bytes(names: &str, target_os: TargetOs) -> Vec<u8> {
+    let names = names.as_bytes();
+    let mut bytes = Vec::with_capacity(names.len());
+    let mut pos = 0;
+    while pos < names.len() {
+        if let Some(byte) = decodable_byte(&names[pos..], target_os) {
+            bytes.push(byte);
+            pos += 3;
+        } else {
+            bytes.push(names[pos]);
+            pos += 1;
+        }
+    }
+    bytes
+}
+
+/// Returns the decoded byte of the escape at the start, if it is allowed inside a name on the target OS.
+fn decodable_byte(bytes: &[u8], target_os: TargetOs) -> Option<u8> {
+    if !is_escape_sequence(bytes) {
+        return None;
+    }
+    let hex = std::str::from_utf8(&bytes[1..3]).expect("hex digits are ASCII");
+    let byte = u8::from_str_radix(hex, 16).expect("hex digits are checked");
+    let is_separator = byte == b'/' || (byte == b'\\' && target_os == TargetOs::Windows);
+    (!is_separator && byte != 0).then_some(byte)
+}
+
+/// On Linux the path is just bytes, so the non-utf8 names are restored exactly.
+#[cfg(unix)]
+fn path_buf_from_bytes(bytes: Vec<u8>) -> std::path::PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    std::path::PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
+
+/// Other OS cannot store arbitrary bytes, so the invalid utf8 is replaced.
+#[cfg(not(unix))]
+fn path_buf_from_bytes(bytes: Vec<u8>) -> std::path::PathBuf {
+    std::path::PathBuf::from(String::from_utf8_lossy(&bytes).to_string())
+}
@@ -48,24 +48,32 @@
 //!     0-31 (ASCII control characters)  
 //!     ```
 //!   
-//! 5. Filenames cannot end in a space or dot.
+//! 5. File and folder names cannot end in a space or dot.
 //! 6. Not allow reserved filenames even with extensions and foldernames:  
-//!    CON, PRN, AUX, NUL  
-//!    COM1, COM2, COM3, COM4, COM5, COM6, COM7, COM8, COM9  
-//!    LPT1, LPT2, LPT3, LPT4, LPT5, LPT6, LPT7, LPT8, LPT9  
+//!    CON, PRN, AUX, NUL, CONIN$, CONOUT$  
+//!    COM0, COM1, COM2, COM3, COM4, COM5, COM6, COM7, COM8, COM9, COM¹, COM², COM³  
+//!    LPT0, LPT1, LPT2, LPT3, LPT4, LPT5, LPT6, LPT7, LPT8, LPT9, LPT¹, LPT², LPT³  
+//!    Every component is checked, so `con.txt`, `nul.tar.gz` and `COM1 .txt` are not allowed, but `console` is allowed.  
 //!    These names are not really needed and will not be allowed:  
 //!    .  (special name referring to current directory)  
 //!    This have to be avoided because of traversal attacks:  
 //!    .. (special name referring to parent directory)  
+//!    The opt-in normalize_lexically() resolves them without climbing above the start or the anchor.  
 //!
 //! 7. Instead of the problematic Windows 'c:' or 'd:' drives,  
 //!    the neutral crossplatform format will be '/mnt/c' or '/mnt/d'  
 //!    From Windows:  
 //!    c:\\ will be transformed into /mnt/c/  
 //!    d:\\ will be transformed into /mnt/d/  
+//!    From MSYS2 and Cygwin:  
+//!    /cygdrive/c/ will be transformed into /mnt/c/, /c/ only with the opt-in ValidationProfile::drive_mount_styles  
+//!    Windows network shares \\\\server\\share will be transformed into //server/share  
 //! 8. This special symbols and root folders are allowed and will be transformed for Windows:  
 //!    '~'    will be transformed into %UserProfile%  
 //!    /tmp   will be transformed into %TEMP%  
+//!    '~user' will be transformed into the home folder of that user from the ResolveContext  
+//!    The anchors are recognized only as whole leading components, so /tmpdata and data/~ stay normal names.  
+//!    Other mounts like /data/shared to S:\\ can be added with a PathMapping in the ResolveContext or for the whole process with PathMapping::set_process_default() once at startup.  
 //! 9. Definitely some paths in one OS have absolutely no meaning in other OS, but these have to be avoided manually.
 //!
 //! ## Usage
@@ -140,26 +148,56 @@
 //!
 // endregion: auto_md_to_doc_comments include README.md A //!
 
-use std::ffi::OsStr;
-
+mod case_insensitive;
+mod components;
+#[cfg(feature = "serde")]
+mod cross_path_serde;
+mod cross_root;
+mod env_expand;
+mod escape;
+mod path_mapping;
+mod relative;
+mod resolve_context;
+mod sanitize;
 #[cfg(test)]
 mod tests;
+mod validation;
 
 /// All possible library errors for `thiserror`.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
-    #[error(r#"The string {0} contains an invalid windows path character < > : " | ? * "#)]
-    InvalidCharacter(String),
-    #[error(r#"The string {0} contains forbidden ascii control character for windows path 0-31 "#)]
-    ForbiddenAscii(String),
-    #[error(r#"The path string {0} must not end with space or dot "#)]
-    MustNotEndWith(String),
-    #[error(r#"The path string {0} must not contain reserved words con, prn, aux, nul, com1-com9, lpt1-lpt9, . and .."#)]
-    ReservedWords(String),
+    #[error(r#"The string {} contains an invalid windows path character < > : " | ? * "#, .0.path)]
+    InvalidCharacter(Violation),
+    #[error(r#"The string {} contains forbidden ascii control character for windows path 0-31 "#, .0.path)]
+    ForbiddenAscii(Violation),
+    #[error(r#"The path string {} must not end with space or dot "#, .0.path)]
+    MustNotEndWith(Violation),
+    #[error(r#"The path string {} contains an empty name between two slashes at byte {} "#, .0.path, .0.byte_offset)]
+    EmptyComponent(Violation),
+    #[error(r#"The path string {} must not contain reserved words con, prn, aux, nul, conin$, conout$, com0-com9, lpt0-lpt9, com¹-com³, lpt¹-lpt³ even with extension, . and .."#, .0.path)]
+    ReservedWords(Violation),
+    #[error(r#"The path string {} contains a character outside of the POSIX portable filename character set A-Z a-z 0-9 . _ - "#, .0.path)]
+    NotPortable(Violation),
+    #[error(r#"The path string {} contains a bidirectional, invisible or C1 control character "#, .0.path)]
+    UnsafeUnicode(Violation),
+    #[error(r#"The path string {} has .. that climbs above the start of the path or above the anchor "#, .0.path)]
+    ClimbsAboveStart(Violation),
+    #[error("The path {path} escapes the root {root}.")]
+    EscapesRoot { path: String, root: String },
+    #[error("The path {path} is not under the base {base}.")]
+    NotUnderBase { path: String, base: String },
     #[error(r#"The parent of {0} does not exist."#)]
     NoParent(String),
     #[error(r#"The file_name of {0} does not exist."#)]
     NoFileName(String),
+    #[error(r#"The environment variable {0} is not set."#)]
+    EnvVarNotSet(String),
+    #[error(r#"The home folder of the user {0} is not in the ResolveContext."#)]
+    UnknownUser(String),
+    #[error(r#"The path mapping rule {0} is invalid. It must be: target_os absolute_crossplatform_prefix => absolute_native_prefix"#)]
+    InvalidMapping(String),
+    #[error(r#"The process default path mapping can be set only once, before it is used."#)]
+    ProcessDefaultAlreadySet,
     #[error(r#"char_indices().nth error {0}"#)]
     CharIndicesNthError(String),
     #[error("I/O error: {path} {source}")]
@@ -168,10 +206,23 @@ pub enum Error {
         source: std::io::Error,
         path: String,
     },
+    #[error("The path {path} is too long: {length} is over the limit {limit}.")]
+    TooLong { path: String, length: usize, limit: usize },
     #[error("Unknown error.")]
     Unknown,
 }
 
+use unicode_normalization::UnicodeNormalization;
+
+pub use case_insensitive::{CaseInsensitive, CaseSensitivity};
+pub use components::{Ancestors, Components, CrossComponent, Iter};
+pub use cross_root::CrossRoot;
+pub use path_mapping::{MappingRule, PathMapping};
+pub use relative::ParentTraversal;
+pub use resolve_context::{DriveMountStyle, LengthLimits, ResolveContext, TargetOs};
+pub use sanitize::SanitizeOptions;
+pub use validation::{DotComponents, ValidationProfile, ValidationReport, ValidationRule, Violation};
+
 /// crossplatform_path::Result
 ///
 /// `crossplatform_path::Result` is used with just one parameter.
@@ -186,14 +237,32 @@ pub type Result<T, E = Error> = core::result::Result<T, E>;
 /// When used for file operations, this Neutral format is converted into Linux or Windows format accordingly.  \
 /// Some limitations exist for paths mostly because of Windows limitations:  \
 /// forbidden characters < > : " / \\ | ? *  0 (NULL byte)  0-31 (ASCII control characters)  \
-/// Filenames cannot end in a space or dot.  \
+/// File and folder names cannot end in a space or dot.  \
 /// Separator is always slash. Backslash is replaced. Backslash must never be a part of a name or path component.  \
-/// Must not contain reserved words con, prn, aux, nul, com1-com9, lpt1-lpt9, . and ..  \
-/// If starts with windows c: or d: it is converted to /mnt/c or /mnt/d lowercase  
-#[derive(Clone, Debug, PartialEq)]
+/// Must not contain reserved words con, prn, aux, nul, conin$, conout$, com0-com9, lpt0-lpt9, . and .. in any component, even with extension like con.txt  \
+/// If starts with windows c: or d: it is converted to /mnt/c or /mnt/d lowercase  \
+/// CrossPathBuf derefs to the borrowed CrossPath, where all the read-only methods are implemented.  \
+/// It also carries the path resolved for the current OS, so it implements `AsRef<Path>` and can be passed to std::fs directly.
+#[derive(Clone, Debug)]
 pub struct CrossPathBuf {
     /// Path stored in a Neutral Crossplatform format.
     cross_path: String,
+    /// The same path converted into the current OS format.  \
+    /// The '~' and /tmp expansion is done once in the constructor and reused by all the file operations.
+    native_path: std::path::PathBuf,
+    /// The path was created by from_path_escaped() or new_escaped(), so the `%XX` in the names are decoded in the native path.
+    escaped: bool,
+}
+
+/// CrossPath is the borrowed slice of a CrossPathBuf, like Path is for PathBuf.  \
+///
+/// It is an unsized type and it is always used behind a reference `&CrossPath`.  \
+/// It always contains a valid path in the Neutral Crossplatform format.  \
+/// Functions can accept `&CrossPath` and methods like parent() return a borrowed sub-slice without allocation.
+#[repr(transparent)]
+pub struct CrossPath {
+    /// Path stored in a Neutral Crossplatform format.
+    cross_path: str,
 }
 
 impl CrossPathBuf {
@@ -207,217 +276,557 @@ impl CrossPathBuf {
     /// When used for file operations, this Neutral format is converted into Linux or Windows format accordingly.  \
     /// Some limitations exist for paths mostly because of Windows limitations:  \
     /// forbidden characters < > : " / \\ | ? *  0 (NULL byte)  0-31 (ASCII control characters)  \
-    /// Filenames cannot end in a space or dot.  \
+    /// File and folder names cannot end in a space or dot.  \
     /// Separator is always slash. Backslash is replaced. Backslash must never be a part of a name or path component.  \
-    /// Must not contain reserved words con, prn, aux, nul, com1-com9, lpt1-lpt9, . and ..  \
-    /// If start with windows c: or d: convert to /mnt/c or /mnt/d lowercase  
+    /// Must not contain reserved words con, prn, aux, nul, conin$, conout$, com0-com9, lpt0-lpt9, . and .. in any component, even with extension like con.txt  \
+    /// If start with windows c: or d: convert to /mnt/c or /mnt/d lowercase  \
+    /// Drives mounted like Cygwin /cygdrive/c are converted to /mnt/c. MSYS2 /c only with the opt-in ValidationProfile::drive_mount_styles  
     pub fn new(str_path: &str) -> Result<Self> {
-        // forbidden: < > : " / \\ | ? *  0 (NULL byte)  0-31 (ASCII control characters)
-        // but : / and \\ are delimiters and can be used in a path fragment with multiple components.
-        if str_path.contains("<")
-            || str_path.contains(">")
-            || str_path.contains(r#"""#)
-            || str_path.contains("|")
-            || str_path.contains("?")
-            || str_path.contains("*")
-        {
-            return Err(Error::InvalidCharacter(str_path.to_string()));
-        }
-        // 0 (NULL byte) and  0-31 (ASCII control characters) 127 is DEL
-        // Important: utf8 is always on a byte level compatible with ASCII7, under 127.
-        for byte in str_path.bytes() {
-            match byte {
-                0x00..=0x1F | 0x7F => return Err(Error::ForbiddenAscii(str_path.to_string())),
-                _ => (),
-            }
-        }
-        //Filenames cannot end in a space or dot.
-        if str_path.ends_with(" ") || str_path.ends_with(".") {
-            return Err(Error::MustNotEndWith(str_path.to_string()));
-        }
+        Self::new_with_profile(str_path, &ValidationProfile::default())
+    }
 
+    /// Creates a new CrossPathBuf from &str validated with the rules of the profile.  \
+    ///
+    /// The same as new(), but the rules are selected with the ValidationProfile.  \
+    /// The methods that create a new path like join_relative() or replace_extension() validate the result with the default profile.  \
+    /// With profile.normalize_nfc the string is normalized to Unicode NFC first, so the offsets in the errors are for the normalized string.
+    pub fn new_with_profile(str_path: &str, profile: &ValidationProfile) -> Result<Self> {
+        // macOS creates names in NFD and Linux usually in NFC. The normalization is before the validation.
+        let normalized;
+        let str_path = if profile.normalize_nfc {
+            normalized = str_path.nfc().collect::<String>();
+            &normalized
+        } else {
+            str_path
+        };
+        // All the rules are checked in validate_with_profile(). The first violation is returned as the Error.
+        Self::validate_with_profile(str_path, profile).into_result()?;
+        // The extended-length prefix \\?\ is not a part of the path and the ? would be forbidden.
+        let str_path = &strip_extended_length_prefix(str_path);
         // Separator is always slash. Backslash is replaced. Backslash must never be a part of a name or path component.
-        // trim: leading and trailing whitespace removed
-        let mut cross_path = str_path.trim().replace(r#"\"#, "/");
-
-        // 6. Not allow reserved filenames even with extensions and foldernames:
-        // Windows path is case insensitive, so I must check insensitive. I will use to_lowercase.
-        // CON, PRN, AUX, NUL
-        // COM1, COM2, COM3, COM4, COM5, COM6, COM7, COM8, COM9
-        // LPT1, LPT2, LPT3, LPT4, LPT5, LPT6, LPT7, LPT8, LPT9
-        // These names are not really needed and will not be allowed:
-        // .  (special name referring to current directory)
-        // This have to be avoided because of traversal attacks:
-        // .. (special name referring to parent directory)
-        // I need to delimit start and end with / to use contains.
-        let delimited_str_path = format!("/{}/", cross_path.trim_start_matches("/").trim_end_matches("/").to_lowercase());
-        if delimited_str_path.contains("/con/")
-            || delimited_str_path.contains("/prn/")
-            || delimited_str_path.contains("/aux/")
-            || delimited_str_path.contains("/nul/")
-            || delimited_str_path.contains("/com1/")
-            || delimited_str_path.contains("/com2/")
-            || delimited_str_path.contains("/com3/")
-            || delimited_str_path.contains("/com4/")
-            || delimited_str_path.contains("/com5/")
-            || delimited_str_path.contains("/com6/")
-            || delimited_str_path.contains("/com7/")
-            || delimited_str_path.contains("/com8/")
-            || delimited_str_path.contains("/com9/")
-            || delimited_str_path.contains("/lpt1/")
-            || delimited_str_path.contains("/lpt2/")
-            || delimited_str_path.contains("/lpt3/")
-            || delimited_str_path.contains("/lpt4/")
-            || delimited_str_path.contains("/lpt5/")
-            || delimited_str_path.contains("/lpt6/")
-            || delimited_str_path.contains("/lpt7/")
-            || delimited_str_path.contains("/lpt8/")
-            || delimited_str_path.contains("/lpt9/")
-            || delimited_str_path.contains("/./")
-            || delimited_str_path.contains("/../")
-        {
-            return Err(Error::ReservedWords(str_path.to_string()));
-        }
+        // trim: leading whitespace removed. A trailing space is not allowed in any component.
+        let mut cross_path = str_path.trim_start().replace(r#"\"#, "/");
 
         // If start with windows c: or d: convert to /mnt/c or /mnt/d lowercase
+        // All drive letters a-z are allowed. The drive relative c:foo is the same as c:/foo.
+        // Without profile.forbid_colon the colon is a normal character, so `a:b` stays a relative Linux name.
         let mut iter = cross_path.chars();
-        if let Some(first) = iter.next()
+        if profile.forbid_colon
+            && let Some(first) = iter.next()
+            && first.is_ascii_alphabetic()
             && let Some(second) = iter.next()
             && second == ':'
         {
-            cross_path = format!("/mnt/{}{}", first.to_lowercase(), iter.as_str());
+            let rest = iter.as_str();
+            let separator = if rest.is_empty() || rest.starts_with('/') { "" } else { "/" };
+            cross_path = format!("/mnt/{}{separator}{rest}", first.to_ascii_lowercase());
         }
 
-        // Forbidden character, except for windows drive
-        if cross_path.contains(":") {
-            return Err(Error::InvalidCharacter(cross_path));
+        // Drives mounted like WSL /mnt/c, Cygwin /cygdrive/c and the opt-in MSYS2 /c are normalized to /mnt/c
+        let mounted_drive = validation::split_mounted_drive(&cross_path, profile)
+            .map(|(letter, rest)| format!("/mnt/{}{rest}", letter.to_ascii_lowercase()));
+        if let Some(mounted_drive) = mounted_drive {
+            cross_path = mounted_drive;
         }
 
-        // Forbidden double slash
-        if cross_path.contains("//") {
-            return Err(Error::InvalidCharacter(cross_path));
+        // The opt-in lexical resolution of . and .. was already checked in the validation.
+        if profile.dot_components != DotComponents::Reject {
+            cross_path = resolve_dot_components(&cross_path);
         }
 
-        Ok(CrossPathBuf { cross_path })
+        Ok(Self::from_valid_string(cross_path))
+    }
+
+    /// Creates a new CrossPathBuf and resolves the special names `.` and `..` lexically.  \
+    ///
+    /// The `.` is removed and `..` removes the preceding name, without looking at the filesystem.  \
+    /// It returns Error::ClimbsAboveStart if `..` would climb above the start of a relative path
+    /// or above the anchor `/`, `~`, `/mnt/c`, `/tmp` or `//server/share`,
+    /// and also if the resolved names would start with an anchor that was not in the path, like `a/../~/.ssh` or `/x/../tmp`.  \
+    /// To keep the leading `..` of a relative path like `../shared/assets`,
+    /// use new_with_profile() with DotComponents::ResolveKeepLeading.
+    ///
+    /// ```rust
+    /// use crossplatform_path::CrossPathBuf;
+    ///
+    /// assert_eq!(CrossPathBuf::normalize_lexically("./data/../assets/./img")?, "assets/img");
+    /// assert!(CrossPathBuf::normalize_lexically("~/../etc").is_err());
+    /// # Ok::<(), crossplatform_path::Error>(())
+    /// ```
+    pub fn normalize_lexically(str_path: &str) -> Result<Self> {
+        let profile = ValidationProfile {
+            dot_components: DotComponents::Resolve,
+            ..ValidationProfile::default()
+        };
+        Self::new_with_profile(str_path, &profile)
     }
 
     /// Creates a new CrossPathBuf from &Path.  \
+    ///
+    /// The process default PathMapping for the current OS is applied, so `S:\projects` can become `/data/shared/projects`.
     pub fn from_path(path: &std::path::Path) -> Result<Self> {
-        let str_path = path
-            .to_str()
-            .ok_or_else(|| Error::InvalidCharacter(path.to_string_lossy().to_string()))?;
+        let cross_path = Self::from_path_unmapped(path)?;
+        match ResolveContext::process()
+            .path_mapping
+            .map_to_cross(&cross_path, TargetOs::current())
+        {
+            Some(mapped) => Ok(mapped),
+            None => Ok(cross_path),
+        }
+    }
+
+    /// Creates a new CrossPathBuf from &Path without the path mapping.
+    fn from_path_unmapped(path: &std::path::Path) -> Result<Self> {
+        let str_path = path.to_str().ok_or_else(|| {
+            // The invalid utf8 is replaced with U+FFFD, so the position can be shown.
+            let lossy = path.to_string_lossy();
+            let offset = lossy.find('\u{FFFD}').unwrap_or(0);
+            Error::InvalidCharacter(Violation::new(
+                ValidationRule::InvalidCharacter,
+                &lossy,
+                offset,
+                0,
+                Some('\u{FFFD}'),
+            ))
+        })?;
         Self::new(str_path)
     }
 
-    /// Converts crossplatform path into Windows path.  \
+    /// Creates a new CrossPathBuf from &Path and escapes the names that the neutral format cannot represent.  \
     ///
-    /// '~'     will be transformed into home  \
-    /// /mnt/c/ will be transformed into c:\\  \
-    /// /mnt/d/ will be transformed into d:\\  \
-    /// /tmp    will be transformed into %TEMP%  
-    pub fn to_path_buf_win(&self) -> std::path::PathBuf {
-        let mut win_path = self.cross_path.clone();
-        // '~'    will be transformed into home
-        if win_path.starts_with("~")
-            && let Some(home) = std::env::home_dir()
-        {
-            win_path = format!("{}{}", home.to_string_lossy(), win_path.trim_start_matches("~"));
+    /// Forbidden characters, control characters, trailing dots and spaces, reserved names and non-utf8 bytes
+    /// are written as `%XX` with uppercase hex digits. A literal `%` before two uppercase hex digits is escaped as `%25`.  \
+    /// The conversion to the native path with to_path_buf_nix() or to_path_buf_with() decodes the escapes,
+    /// so the native path is byte-identical to the original. The path is marked as escaped, see is_escaped().
+    /// The paths from new() are never decoded. To read back a stored escaped path use new_escaped().
+    ///
+    /// ```rust
+    /// # #[cfg(unix)] {
+    /// use crossplatform_path::CrossPathBuf;
+    ///
+    /// let cross_path = CrossPathBuf::from_path_escaped(std::path::Path::new("/data/report: Q1?.txt"))?;
+    /// assert_eq!(cross_path.as_str(), "/data/report%3A Q1%3F.txt");
+    /// assert_eq!(cross_path.to_path_buf_nix().to_string_lossy(), "/data/report: Q1?.txt");
+    /// # }
+    /// # Ok::<(), crossplatform_path::Error>(())
+    /// ```
+    pub fn from_path_escaped(path: &std::path::Path) -> Result<Self> {
+        Self::new_escaped(&escape::escape_native_path(path))
+    }
+
+    /// Creates a new CrossPathBuf from &str with the reversible escapes like `%3A` from from_path_escaped().  \
+    ///
+    /// Use it to read back the escaped path stored in a config file.
+    /// The paths from new() are never decoded, so `report%20final.pdf` stays a literal name there.  \
+    /// The escape `%2F` for the slash and `%00` are never decoded. On Windows `%5C` for the backslash is not decoded.  \
+    /// The methods of CrossPathBuf like join_relative(), parent() or replace_extension() keep the flag. Extend keeps the flag of the first path.  \
+    /// The borrowed CrossPath has no flag, so its methods return paths that are not escaped.
+    ///
+    /// ```rust
+    /// use crossplatform_path::CrossPathBuf;
+    ///
+    /// let cross_path = CrossPathBuf::new_escaped("/data/report%3A Q1%3F.txt")?;
+    /// assert!(cross_path.is_escaped());
+    /// assert_eq!(cross_path.to_path_buf_nix().to_string_lossy(), "/data/report: Q1?.txt");
+    /// let literal = CrossPathBuf::new("/data/report%20final.pdf")?;
+    /// assert_eq!(literal.to_path_buf_nix().to_string_lossy(), "/data/report%20final.pdf");
+    /// # Ok::<(), crossplatform_path::Error>(())
+    /// ```
+    pub fn new_escaped(str_path: &str) -> Result<Self> {
+        let cross_path = Self::new(str_path)?;
+        Ok(Self::from_valid_string_escaped(cross_path.cross_path, true))
+    }
+
+    /// Returns `true` if the path was created by from_path_escaped() or new_escaped().  \
+    ///
+    /// Only these paths decode the `%XX` escapes into the native path.
+    /// The flag is compared in Eq and Hash, because the native paths differ. It is not a part of the neutral string, so it is not serialized.
+    pub fn is_escaped(&self) -> bool {
+        self.escaped
+    }
+
+    /// Creates a new CrossPathBuf from &Path and replaces the home folder with `~` and the Windows temp folder with `/tmp`.  \
+    ///
+    /// It is the reverse of the anchor expansion in to_path_buf_with(), so the stored path works on the other OS.  \
+    /// The home and temp folders are taken from the context. The home folders of other users become `~user`.
+    /// Only TargetOs::Windows expands `/tmp` into the temp folder, so on Linux and macOS the temp folder like `/var/folders/ab/T` stays unchanged.
+    /// If more folders match, the longer one is used,
+    /// because the Windows temp folder is usually inside the home folder.  \
+    /// For TargetOs::Windows the folders are compared case-insensitive. A folder that is only an anchor like `/` is ignored.
+    ///
+    /// ```rust
+    /// use crossplatform_path::{CrossPathBuf, ResolveContext, TargetOs};
+    ///
+    /// let ctx = ResolveContext {
+    ///     home_dir: Some(r#"C:\Users\alice"#.to_string()),
+    ///     temp_dir: r#"C:\Users\alice\AppData\Local\Temp"#.to_string(),
+    ///     ..ResolveContext::from_env(TargetOs::Windows)
+    /// };
+    /// let path = std::path::Path::new(r#"C:\Users\alice\project\Cargo.toml"#);
+    /// assert_eq!(CrossPathBuf::from_path_neutralized(path, &ctx)?, "~/project/Cargo.toml");
+    /// let path = std::path::Path::new(r#"C:\Users\alice\AppData\Local\Temp\x"#);
+    /// assert_eq!(CrossPathBuf::from_path_neutralized(path, &ctx)?, "/tmp/x");
+    /// # Ok::<(), crossplatform_path::Error>(())
+    /// ```
+    pub fn from_path_neutralized(path: &std::path::Path, ctx: &ResolveContext) -> Result<Self> {
+        let cross_path = Self::from_path_unmapped(path)?;
+        // the user-defined mapping has priority over the home and temp folders
+        if let Some(mapped) = ctx.path_mapping.map_to_cross(&cross_path, ctx.target_os) {
+            return Ok(mapped);
         }
-        // /mnt/c/ will be transformed into c:\\
-        // /mnt/d/ will be transformed into d:\\
-        if win_path.starts_with("/mnt/") {
-            win_path = win_path.trim_start_matches("/mnt/").to_string();
-            win_path.insert(1, ':');
+        let ignore_case = ctx.target_os == TargetOs::Windows;
+        // on equal length the last one wins, so the own home `~` is preferred over `~user`
+        let user_folders = ctx
+            .user_home_dirs
+            .iter()
+            .map(|(user, user_home)| (Some(user_home.as_str()), format!("~{user}")));
+        // the reverse of native_parts_with(), where only Windows expands /tmp
+        let temp_dir = (ctx.target_os == TargetOs::Windows).then_some(ctx.temp_dir.as_str());
+        let folders = user_folders.chain([(temp_dir, "/tmp".to_string()), (ctx.home_dir.as_deref(), "~".to_string())]);
+        let neutralized = folders
+            .filter_map(|(folder, anchor)| {
+                let folder = CrossPathBuf::new(folder?).ok()?;
+                // a relative folder or only an anchor like `/` would match too much
+                let is_absolute = !matches!(folder.components().next(), None | Some(CrossComponent::Normal(_)));
+                if !is_absolute || folder.parent().is_err() {
+                    return None;
+                }
+                let rest = strip_folder(&cross_path, &folder, ignore_case)?;
+                Some((folder.components().count(), anchor, rest))
+            })
+            .max_by_key(|(folder_len, _anchor, _rest)| *folder_len);
+        match neutralized {
+            Some((_folder_len, anchor, rest)) if rest.as_str().is_empty() => Ok(Self::from_valid_string(anchor)),
+            Some((_folder_len, anchor, rest)) => Ok(Self::from_valid_string(format!("{anchor}/{rest}"))),
+            None => Ok(cross_path),
         }
-        // /tmp   will be transformed into %TEMP%
-        if win_path.starts_with("/tmp") {
-            let tmp_dir = std::env::temp_dir();
-            win_path = format!("{}{}", tmp_dir.to_string_lossy(), win_path.trim_start_matches("/tmp"));
+    }
+
+    /// Checks the path string with all the rules of new() and collects all the violations.  \
+    ///
+    /// Every violation has the rule, the byte and char offset, the component index and the offending character,
+    /// so an editor can underline the exact position. The offsets are relative to the input string.
+    ///
+    /// ```rust
+    /// use crossplatform_path::{CrossPathBuf, ValidationRule};
+    ///
+    /// let report = CrossPathBuf::validate("data/con.txt/file?");
+    /// assert!(!report.is_valid());
+    /// assert_eq!(report.violations[0].rule, ValidationRule::InvalidCharacter);
+    /// assert_eq!(report.violations[0].byte_offset, 17);
+    /// assert_eq!(report.violations[1].rule, ValidationRule::ReservedWord);
+    /// assert_eq!(report.violations[1].component_index, 1);
+    /// ```
+    pub fn validate(str_path: &str) -> ValidationReport {
+        Self::validate_with_profile(str_path, &ValidationProfile::default())
+    }
+
+    /// Checks the path string with the rules of the profile and collects all the violations.
+    pub fn validate_with_profile(str_path: &str, profile: &ValidationProfile) -> ValidationReport {
+        validation::validate_path(str_path, profile)
+    }
+
+    /// Turns arbitrary text like a user title or an external ID into one valid file or folder name.  \
+    ///
+    /// Forbidden characters and separators are replaced, control bytes are stripped,
+    /// invisible and bidirectional characters are stripped if the profile has forbid_unsafe_unicode,
+    /// trailing dots and spaces are removed, reserved device names get a prefix and the result is truncated on a char boundary.  \
+    /// The result always passes the same validation as new() with the profile from the options.
+    ///
+    /// ```rust
+    /// use crossplatform_path::{CrossPathBuf, SanitizeOptions};
+    ///
+    /// assert_eq!(CrossPathBuf::sanitize_component("Report: Q1/Q2?.", SanitizeOptions::default()), "Report_ Q1_Q2_");
+    /// assert_eq!(CrossPathBuf::sanitize_component("con.txt", SanitizeOptions::default()), "_con.txt");
+    /// ```
+    pub fn sanitize_component(text: &str, options: SanitizeOptions) -> String {
+        sanitize::sanitize_component(text, &options)
+    }
+
+    /// Returns the deepest common ancestor of all the paths.  \
+    ///
+    /// The comparison is component-wise, so the common ancestor of `/home/user1` and `/home/user2` is `/home`.  \
+    /// Returns None for an empty slice, for paths with different anchors like `~` and `/home`
+    /// and for a mix of absolute and relative paths.
+    ///
+    /// ```rust
+    /// use crossplatform_path::CrossPathBuf;
+    ///
+    /// let paths = ["~/project/src/lib.rs", "~/project/src/main.rs", "~/project/README.md"].map(|path| CrossPathBuf::new(path).unwrap());
+    /// assert_eq!(CrossPathBuf::common_ancestor(&paths).unwrap(), "~/project");
+    /// ```
+    pub fn common_ancestor(paths: &[CrossPathBuf]) -> Option<CrossPathBuf> {
+        let (first, others) = paths.split_first()?;
+        let is_relative = |cross_path: &CrossPathBuf| matches!(cross_path.components().next(), None | Some(CrossComponent::Normal(_)));
+        first
+            .ancestors()
+            .find(|ancestor| {
+                others.iter().all(|other| other.starts_with(ancestor))
+                    // the empty path is the common start only of relative paths
+                    && (!ancestor.as_str().is_empty() || paths.iter().all(is_relative))
+            })
+            .map(|ancestor| first.keep_escaped(ancestor.to_cross_path_buf()))
+    }
+
+    /// Wraps a String that is already in the Neutral Crossplatform format.  \
+    ///
+    /// Private, because the string is not validated.
+    /// The native path for the current OS is resolved here once.
+    fn from_valid_string(cross_path: String) -> Self {
+        Self::from_valid_string_escaped(cross_path, false)
+    }
+
+    /// Wraps a String that is already in the Neutral Crossplatform format and marks if it contains escapes.
+    fn from_valid_string_escaped(cross_path: String, escaped: bool) -> Self {
+        let borrowed = CrossPath::from_valid_str(&cross_path);
+        // the context is read from the environment only once, so it is cheap to borrow it for every path
+        let ctx = ResolveContext::process();
+        let native_path = if escaped {
+            borrowed.to_path_buf_unescaped_with(ctx)
+        } else {
+            borrowed.to_path_buf_with(ctx)
+        };
+        CrossPathBuf {
+            cross_path,
+            native_path,
+            escaped,
         }
-        use std::str::FromStr;
-        std::path::PathBuf::from_str(&win_path).expect("PathBuf::from_str() returns Infallible error. Therefore the error cannot occur.")
     }
 
-    /// Converts crossplatform path into Linux path.  \
+    /// Converts the path into Windows path and decodes the escapes, if the path is escaped.  \
+    ///
+    /// See CrossPath::to_path_buf_win().
+    pub fn to_path_buf_win(&self) -> std::path::PathBuf {
+        self.to_path_buf_with(&ResolveContext::from_env(TargetOs::Windows))
+    }
+
+    /// Converts the path into Linux path and decodes the escapes like `%3A`, if the path is escaped.  \
     ///
-    /// '~'    will be transformed into home  
+    /// See CrossPath::to_path_buf_nix().
     pub fn to_path_buf_nix(&self) -> std::path::PathBuf {
-        let mut nix_path = self.cross_path.clone();
-        // '~'    will be transformed into home
-        if nix_path.starts_with("~")
-            && let Some(home) = std::env::home_dir()
-        {
-            nix_path = format!("{}{}", home.to_string_lossy(), nix_path.trim_start_matches("~"));
-        }
-        use std::str::FromStr;
-        std::path::PathBuf::from_str(&nix_path).expect("PathBuf::from_str() returns Infallible error. Therefore the error cannot occur.")
+        self.to_path_buf_with(&ResolveContext::from_env(TargetOs::Linux))
     }
 
-    /// Converts crossplatform path into current OS path.  \
+    /// Converts the path into current OS path and decodes the escapes, if the path is escaped.  \
     ///
-    /// '~'     will be transformed into home  \
-    /// /mnt/c/ will be transformed into c:\\  \
-    /// /mnt/d/ will be transformed into d:\\  \
-    /// /tmp    will be transformed into %TEMP%  
+    /// The same as as_native_path(), but it returns an owned PathBuf.
     pub fn to_path_buf_current_os(&self) -> std::path::PathBuf {
-        if cfg!(windows) {
-            self.to_path_buf_win()
+        self.native_path.clone()
+    }
+
+    /// Converts the path into a path for the target OS of the context and decodes the escapes, if the path is escaped.  \
+    ///
+    /// See CrossPath::to_path_buf_with(). The prefix from the context like the home folder is never decoded.
+    pub fn to_path_buf_with(&self, ctx: &ResolveContext) -> std::path::PathBuf {
+        if self.escaped {
+            self.as_cross_path().to_path_buf_unescaped_with(ctx)
         } else {
-            self.to_path_buf_nix()
+            self.as_cross_path().to_path_buf_with(ctx)
         }
     }
 
-    /// Returns the crossplatform str for use in Display and store into config files.
-    pub fn as_str(&self) -> &str {
-        &self.cross_path
+    /// Marks the new path as escaped, if this path is escaped.
+    fn keep_escaped(&self, cross_path: CrossPathBuf) -> CrossPathBuf {
+        if self.escaped && !cross_path.escaped {
+            Self::from_valid_string_escaped(cross_path.cross_path, true)
+        } else {
+            cross_path
+        }
+    }
+
+    /// Returns the parent path and keeps the escaped flag.  \
+    ///
+    /// The same as CrossPath::parent(), but owned, because the borrowed CrossPath has no escaped flag.
+    pub fn parent(&self) -> Result<CrossPathBuf> {
+        Ok(self.keep_escaped(self.as_cross_path().parent()?.to_cross_path_buf()))
+    }
+
+    /// Returns the path without leading start slash and keeps the escaped flag.
+    pub fn trim_start_slash(&self) -> Result<CrossPathBuf> {
+        Ok(self.keep_escaped(self.as_cross_path().trim_start_slash()?.to_cross_path_buf()))
+    }
+
+    /// Returns the path without trailing end slash and keeps the escaped flag.
+    pub fn trim_end_slash(&self) -> Result<CrossPathBuf> {
+        Ok(self.keep_escaped(self.as_cross_path().trim_end_slash()?.to_cross_path_buf()))
+    }
+
+    /// Returns the path with one leading start slash and keeps the escaped flag.
+    pub fn add_start_slash(&self) -> Result<CrossPathBuf> {
+        Ok(self.keep_escaped(self.as_cross_path().add_start_slash()?))
+    }
+
+    /// Returns the path with one trailing end slash and keeps the escaped flag.
+    pub fn add_end_slash(&self) -> Result<CrossPathBuf> {
+        Ok(self.keep_escaped(self.as_cross_path().add_end_slash()?))
+    }
+
+    /// Joins the relative path and keeps the escaped flag.  \
+    ///
+    /// The joined str_path of an escaped path is in the escaped format too, so a literal `%41` must be written as `%2541`.
+    pub fn join_relative(&self, str_path: &str) -> Result<CrossPathBuf> {
+        Ok(self.keep_escaped(self.as_cross_path().join_relative(str_path)?))
+    }
+
+    /// Replaces the extension and keeps the escaped flag.
+    pub fn replace_extension(&self, extension: &str) -> Result<CrossPathBuf> {
+        Ok(self.keep_escaped(self.as_cross_path().replace_extension(extension)?))
+    }
+
+    /// Expands the environment variables and keeps the escaped flag.  \
+    ///
+    /// See CrossPath::expand_env(). In an escaped path `%` before two uppercase hex digits is an escape and never a variable.
+    pub fn expand_env(&self) -> Result<CrossPathBuf> {
+        self.expand_env_with(|name| std::env::var(name).ok())
+    }
+
+    /// Expands the variables with values from the lookup function and keeps the escaped flag.  \
+    ///
+    /// See CrossPath::expand_env_with(). In an escaped path `%` before two uppercase hex digits is an escape and never a variable.
+    pub fn expand_env_with(&self, lookup: impl Fn(&str) -> Option<String>) -> Result<CrossPathBuf> {
+        let expanded = env_expand::expand_variables(self.as_str(), &lookup, self.escaped)?;
+        Ok(self.keep_escaped(CrossPathBuf::new(&expanded)?))
+    }
+
+    /// Replaces the anchors from the context and keeps the escaped flag.
+    pub fn expand_anchors_with(&self, ctx: &ResolveContext) -> Result<CrossPathBuf> {
+        Ok(self.keep_escaped(self.as_cross_path().expand_anchors_with(ctx)?))
+    }
+
+    /// Returns the rest of the path after the base and keeps the escaped flag.
+    pub fn strip_prefix(&self, base: &CrossPath) -> Result<CrossPathBuf> {
+        Ok(self.keep_escaped(self.as_cross_path().strip_prefix(base)?.to_cross_path_buf()))
+    }
+
+    /// Returns this path relative to the base and keeps the escaped flag.
+    pub fn relative_to(&self, base: &CrossPath) -> Result<CrossPathBuf> {
+        Ok(self.keep_escaped(self.as_cross_path().relative_to(base)?))
+    }
+
+    /// Returns this path relative to the base, optionally with `../`, and keeps the escaped flag.
+    pub fn relative_to_with(&self, base: &CrossPath, parent_traversal: ParentTraversal) -> Result<CrossPathBuf> {
+        Ok(self.keep_escaped(self.as_cross_path().relative_to_with(base, parent_traversal)?))
+    }
+
+    /// Returns a copy of the path with the escaped flag. The same as clone().
+    pub fn to_cross_path_buf(&self) -> CrossPathBuf {
+        self.clone()
+    }
+
+    /// Renders the path as a string for the target OS and decodes the escapes, if the path is escaped.  \
+    ///
+    /// See CrossPath::to_string_for(). The bytes that are not utf8 are replaced with U+FFFD.
+    pub fn to_string_for(&self, target_os: TargetOs, ctx: &ResolveContext) -> String {
+        self.as_cross_path().render_for(target_os, ctx, self.escaped)
     }
 
-    /// Returns `true` if the path points at an existing entity.
+    /// Returns the borrowed CrossPath. The same as Deref.
+    pub fn as_cross_path(&self) -> &CrossPath {
+        CrossPath::from_valid_str(&self.cross_path)
+    }
+
+    /// Returns the path for the current OS, that was resolved in the constructor.  \
+    ///
+    /// The same as to_path_buf_current_os(), but without allocation.  \
+    /// The home and temp folders are read from the environment only once, when the CrossPathBuf is created.
+    pub fn as_native_path(&self) -> &std::path::Path {
+        &self.native_path
+    }
+
+    /// Returns `true` if the native path points at an existing entity.  \
+    ///
+    /// The escapes of an escaped path are decoded, unlike in CrossPath::exists().
     pub fn exists(&self) -> bool {
-        if cfg!(windows) {
-            self.to_path_buf_win().exists()
-        } else {
-            self.to_path_buf_nix().exists()
-        }
+        self.checked_native_path().is_ok_and(|native_path| native_path.exists())
     }
 
-    /// Returns `true` if the path exists on disk and is pointing at a regular file.
+    /// Returns `true` if the native path exists on disk and is pointing at a regular file.
     pub fn is_file(&self) -> bool {
-        if cfg!(windows) {
-            self.to_path_buf_win().is_file()
-        } else {
-            self.to_path_buf_nix().is_file()
-        }
+        self.checked_native_path().is_ok_and(|native_path| native_path.is_file())
     }
 
-    /// Returns `true` if the path exists on disk and is pointing at a directory.
+    /// Returns `true` if the native path exists on disk and is pointing at a directory.
     pub fn is_dir(&self) -> bool {
-        if cfg!(windows) {
-            self.to_path_buf_win().is_dir()
-        } else {
-            self.to_path_buf_nix().is_dir()
+        self.checked_native_path().is_ok_and(|native_path| native_path.is_dir())
+    }
+
+    /// Returns the native path for the file operations.  \
+    ///
+    /// A `~user` that was not resolved in the constructor returns Error::UnknownUser,
+    /// because the literal `~user` would be a folder relative to the current directory.
+    fn checked_native_path(&self) -> Result<&std::path::Path> {
+        if let Some(CrossComponent::UserHome(user)) = self.components().next()
+            && self.native_path.starts_with(format!("~{user}"))
+        {
+            return Err(Error::UnknownUser(user.to_string()));
         }
+        Ok(&self.native_path)
     }
 
-    /// Joins two paths and returns a new CrossPathBuf to allow function chaining.  \
+    /// Appends a path always as relative. Used by Extend and FromIterator.  \
     ///
-    /// It works differently from the original Rust join() where if the second path is absolute, it overwrites the first path.  \
-    /// Here the second path is always relative and is added to the first path.
-    pub fn join_relative(&self, str_path: &str) -> Result<Self> {
-        let second_path = CrossPathBuf::new(str_path)?;
-        let cross_path = format!(
-            "{}/{}",
-            self.cross_path.trim_end_matches("/"),
-            second_path.as_str().trim_start_matches("/")
-        );
-        Ok(CrossPathBuf { cross_path })
+    /// If this path is empty, the other path is taken as it is, with a possible leading slash.  \
+    /// The `.` and `..` of the other path are resolved lexically. The `..` never removes the anchor or the leading `..` of this path,
+    /// so the result cannot climb above the start of this path. The extra `..` are dropped.  \
+    /// If the resolved names would start with a new anchor like `~` or `/tmp`, the `..` cannot remove the names of this path.  \
+    /// The escaped flag of this path is kept.
+    fn push_relative(&mut self, other: &CrossPath) {
+        if other.as_str().is_empty() {
+            return;
+        }
+        if self.cross_path.is_empty() {
+            *self = Self::from_valid_string_escaped(other.as_str().to_string(), self.escaped);
+            return;
+        }
+        let base = CrossPath::from_valid_str(&self.cross_path);
+        let mut anchor = "";
+        let mut base_names: Vec<&str> = vec![];
+        for (component, text) in base.components().zip(base.iter()) {
+            match component {
+                CrossComponent::Normal(name) => base_names.push(name),
+                _anchor => anchor = text,
+            }
+        }
+        // the `..` can remove the names of this path above the floor
+        let join_names = |floor: usize| -> String {
+            let mut names = base_names.clone();
+            for name in other.as_str().split('/') {
+                match name {
+                    "" | "." => (),
+                    ".." => {
+                        if names.len() > floor {
+                            names.pop();
+                        }
+                    }
+                    name => names.push(name),
+                }
+            }
+            let mut cross_path = anchor.to_string();
+            if !names.is_empty() {
+                if !cross_path.is_empty() && !cross_path.ends_with('/') {
+                    cross_path.push('/');
+                }
+                cross_path.push_str(&names.join("/"));
+                // the trailing slash of a folder is preserved
+                if other.as_str().ends_with('/') {
+                    cross_path.push('/');
+                }
+            }
+            cross_path
+        };
+        let mut cross_path = join_names(base_names.iter().take_while(|name| **name == "..").count());
+        // `a` with `../~/x` would become the anchor `~`, so then the names of this path are kept
+        if validation::starts_with_anchor(&cross_path) && !validation::starts_with_anchor(&self.cross_path) {
+            cross_path = join_names(base_names.len());
+        }
+        *self = Self::from_valid_string_escaped(cross_path, self.escaped);
     }
 
     /// Reads the entire contents of a file into a string.  \
     ///
     /// This is a convenience function based on std::fs::read_to_string  
     pub fn read_to_string(&self) -> Result<String> {
-        let content = std::fs::read_to_string(self.to_path_buf_current_os()).map_err(|err| Error::IoError {
+        let content = std::fs::read_to_string(self.checked_native_path()?).map_err(|err| Error::IoError {
             source: (err),
             path: (self.cross_path.clone()),
         })?;
@@ -430,7 +839,7 @@ impl CrossPathBuf {
     /// It creates the full path directory, if path does not exist.  
     pub fn write_str_to_file(&self, content: &str) -> Result<()> {
         self.create_dir_all_for_file()?;
-        std::fs::write(self.to_path_buf_current_os(), content).map_err(|err| Error::IoError {
+        std::fs::write(self.checked_native_path()?, content).map_err(|err| Error::IoError {
             source: (err),
             path: (self.cross_path.clone()),
         })?;
@@ -443,7 +852,7 @@ impl CrossPathBuf {
     /// It creates the full path directory, if path does not exist.  
     pub fn write_bytes_to_file(&self, content: &[u8]) -> Result<()> {
         self.create_dir_all_for_file()?;
-        std::fs::write(self.to_path_buf_current_os(), content).map_err(|err| Error::IoError {
+        std::fs::write(self.checked_native_path()?, content).map_err(|err| Error::IoError {
             source: (err),
             path: (self.cross_path.clone()),
         })?;
@@ -455,7 +864,7 @@ impl CrossPathBuf {
     /// The cross_path must represent a directory and not a file for this command.
     /// This function is not atomic. If it returns an error, any parent components it was able to create will remain.   
     pub fn create_dir_all(&self) -> Result<()> {
-        std::fs::create_dir_all(self.to_path_buf_current_os()).map_err(|err| Error::IoError {
+        std::fs::create_dir_all(self.checked_native_path()?).map_err(|err| Error::IoError {
             source: (err),
             path: (self.cross_path.clone()),
         })?;
@@ -467,8 +876,10 @@ impl CrossPathBuf {
     /// The cross_path must represent a file. The parent directory will be created.
     /// This function is not atomic. If it returns an error, any parent components it was able to create will remain.   
     pub fn create_dir_all_for_file(&self) -> Result<()> {
-        let path = self.to_path_buf_current_os();
-        let parent = path.parent().ok_or_else(|| Error::NoParent(self.cross_path.clone()))?;
+        let parent = self
+            .checked_native_path()?
+            .parent()
+            .ok_or_else(|| Error::NoParent(self.cross_path.clone()))?;
         std::fs::create_dir_all(parent).map_err(|err| Error::IoError {
             source: (err),
             path: (self.cross_path.clone()),
@@ -476,141 +887,55 @@ impl CrossPathBuf {
         Ok(())
     }
 
-    /// Returns a CrossPathBuf without leading start slash (repeatedly removed).  
-    pub fn trim_start_slash(&self) -> Result<Self> {
-        let cross_path = self.cross_path.trim_start_matches('/').trim().to_string();
-        Ok(CrossPathBuf { cross_path })
-    }
-
-    /// Returns a CrossPathBuf without trailing end slash (repeatedly removed).  
-    pub fn trim_end_slash(&self) -> Result<Self> {
-        let cross_path = self.cross_path.trim_end_matches('/').trim().to_string();
-        Ok(CrossPathBuf { cross_path })
-    }
-
-    /// Returns a CrossPathBuf with one leading start slash.  
-    pub fn add_start_slash(&self) -> Result<Self> {
-        let cross_path = format!("/{}", self.cross_path.trim_start_matches('/').trim());
-        Ok(CrossPathBuf { cross_path })
-    }
-
-    /// Returns a CrossPathBuf with one trailing end slash.  
-    pub fn add_end_slash(&self) -> Result<Self> {
-        let cross_path = format!("{}/", self.cross_path.trim_end_matches('/').trim());
-        Ok(CrossPathBuf { cross_path })
-    }
-
-    /// Returns the final component of the Path, if there is one.
-    ///
-    /// If the path is a normal file, this is the file name.
-    /// If it's the path of a directory, this is the directory name.
-    pub fn file_name(&self) -> Result<String> {
-        let file_name = self
-            .to_path_buf_current_os()
-            .file_name()
-            .ok_or_else(|| Error::NoFileName(self.cross_path.clone()))?
-            .to_string_lossy()
-            .to_string();
-        Ok(file_name)
-    }
-
-    /// Extracts the extension (without the leading dot), if possible.  \
-    ///
-    /// It is different from the std::fs extension() because  \
-    /// it returns an empty string if there is no extension.  \
-    /// It returns Error only if there is no file_name.  
-    pub fn extension(&self) -> Result<String> {
-        // return error if there is no file_name
-        let _file_name = self
-            .to_path_buf_current_os()
-            .file_name()
-            .ok_or_else(|| Error::NoFileName(self.cross_path.clone()))?;
-
-        let file_extension = self
-            .to_path_buf_current_os()
-            .extension()
-            // return empty string if there is no extension
-            .unwrap_or_else(|| OsStr::new(""))
-            .to_string_lossy()
-            .to_string();
-        Ok(file_extension)
-    }
-
-    /// Extracts the stem (non-extension) portion of file_name (the final component of the Path).
-    pub fn file_stem(&self) -> Result<String> {
-        let file_stem = self
-            .to_path_buf_current_os()
-            .file_stem()
-            .ok_or_else(|| Error::NoFileName(self.cross_path.clone()))?
-            .to_string_lossy()
-            .to_string();
-        Ok(file_stem)
-    }
-
-    /// Returns the Path without its final component, if there is one.
-    pub fn parent(&self) -> Result<Self> {
-        CrossPathBuf::new(
-            &self
-                .to_path_buf_current_os()
-                .parent()
-                .ok_or_else(|| Error::NoParent(self.cross_path.clone()))?
-                .to_string_lossy(),
-        )
-    }
-
-    /// Returns new object where the extension is replaced.
-    ///
-    /// If the extension did not exist, it is added.
-    pub fn replace_extension(&self, extension: &str) -> Result<Self> {
-        let old_extension = self.extension()?;
-        let dot_separator = if extension.is_empty() { "" } else { "." };
-        let cross_path = format!(
-            "{}{dot_separator}{extension}",
-            self.cross_path.trim_end_matches(&old_extension).trim_end_matches(".")
-        );
-        CrossPathBuf::new(&cross_path)
-    }
-
-    /// Shorten the crossplatform path to avoid word-wrap for longer paths.
-    pub fn short_string(&self, max_char: u16) -> Result<String> {
-        /// It is used for substring, because string slice are counted in bytes and not chars.  \
-        ///
-        /// If we have multi-byte unicode characters we can get an error if the boundary is not on char boundary.  
-        fn byte_pos_from_chars(text: &str, char_pos: usize) -> Result<usize> {
-            Ok(text
-                .char_indices()
-                .nth(char_pos)
-                .ok_or_else(|| Error::NoFileName(text.to_string()))?
-                .0)
-        }
-        if self.cross_path.chars().count() > max_char as usize {
-            let half_in_char = (max_char / 2 - 2) as usize;
-            let pos1_in_bytes = byte_pos_from_chars(&self.cross_path, half_in_char)?;
-            let pos2_in_bytes = byte_pos_from_chars(&self.cross_path, self.cross_path.chars().count() - half_in_char)?;
-            Ok(format!(
-                "{}...{}",
-                &self.cross_path[..pos1_in_bytes],
-                &self.cross_path[pos2_in_bytes..]
-            ))
-        } else {
-            Ok(self.cross_path.to_string())
-        }
-    }
-
     /// Decompress tar.gz into destination folder.  \
     ///
-    /// It creates the full path destination folder, if path does not exist.  
+    /// It creates the full path destination folder, if path does not exist.  \
+    /// If an entry name contains a bidirectional, invisible or C1 control character,
+    /// it returns Error::UnsafeUnicode and nothing is extracted.
     pub fn decompress_tar_gz(&self, destination_folder: &CrossPathBuf) -> Result<()> {
         destination_folder.create_dir_all()?;
 
-        let tar_gz = std::fs::File::open(self.to_path_buf_current_os()).map_err(|err| Error::IoError {
+        let tar_gz = std::fs::File::open(self.checked_native_path()?).map_err(|err| Error::IoError {
+            source: (err),
+            path: (self.cross_path.clone()),
+        })?;
+        let tar = flate2::read::GzDecoder::new(tar_gz);
+        let mut archive = tar::Archive::new(tar);
+        // The names of all entries are checked before anything is extracted.
+        // Bidirectional and invisible characters can spoof the names in file listings.
+        let profile = ValidationProfile {
+            forbid_unsafe_unicode: true,
+            ..ValidationProfile::permissive_linux()
+        };
+        let entries = archive.entries().map_err(|err| Error::IoError {
+            source: (err),
+            path: (self.cross_path.clone()),
+        })?;
+        for entry in entries {
+            let entry = entry.map_err(|err| Error::IoError {
+                source: (err),
+                path: (self.cross_path.clone()),
+            })?;
+            let entry_name = String::from_utf8_lossy(&entry.path_bytes()).to_string();
+            let report = Self::validate_with_profile(&entry_name, &profile);
+            if let Some(violation) = report
+                .violations
+                .into_iter()
+                .find(|violation| violation.rule == ValidationRule::UnsafeUnicode)
+            {
+                return Err(violation.into());
+            }
+        }
+
+        // The archive is a stream, so it is opened again for the extraction.
+        let tar_gz = std::fs::File::open(self.checked_native_path()?).map_err(|err| Error::IoError {
             source: (err),
             path: (self.cross_path.clone()),
         })?;
         let tar = flate2::read::GzDecoder::new(tar_gz);
         let mut archive = tar::Archive::new(tar);
         archive
-            .unpack(destination_folder.to_path_buf_current_os())
+            .unpack(destination_folder.checked_native_path()?)
             .map_err(|err| Error::IoError {
                 source: (err),
                 path: (self.cross_path.clone()),
@@ -623,7 +948,7 @@ impl CrossPathBuf {
     ///
     /// Note that there is no guarantee that the file is immediately deleted (e.g., depending on platform, other open file descriptors may prevent immediate removal).  
     pub fn remove_file(&self) -> Result<()> {
-        std::fs::remove_file(self.to_path_buf_current_os()).map_err(|err| Error::IoError {
+        std::fs::remove_file(self.checked_native_path()?).map_err(|err| Error::IoError {
             source: (err),
             path: (self.cross_path.clone()),
         })?;
@@ -636,11 +961,11 @@ impl CrossPathBuf {
     /// This function does not follow symbolic links and it will simply remove the symbolic link itself.
     /// DIFFERENCE from std::fs::remove_dir_all: The directory you are deleting does not need to exist.
     pub fn remove_dir_all(&self) -> Result<()> {
-        if std::fs::exists(self.to_path_buf_current_os()).map_err(|err| Error::IoError {
+        if std::fs::exists(self.checked_native_path()?).map_err(|err| Error::IoError {
             source: (err),
             path: (self.cross_path.clone()),
         })? {
-            std::fs::remove_dir_all(self.to_path_buf_current_os()).map_err(|err| Error::IoError {
+            std::fs::remove_dir_all(self.checked_native_path()?).map_err(|err| Error::IoError {
                 source: (err),
                 path: (self.cross_path.clone()),
             })?;
@@ -654,9 +979,17 @@ impl CrossPathBuf {
     /// It creates the full path destination folder, if path does not exist.  
     /// DIFFERENCE from std::fs::copy If the source and destination is the same nothing happens.
     pub fn copy_file_to_file(&self, destination_file: &CrossPathBuf) -> Result<()> {
-        if self.to_path_buf_current_os() != destination_file.to_path_buf_current_os() {
+        self.copy_file_to_file_with(destination_file, CaseSensitivity::Sensitive)
+    }
+
+    /// Copies the contents of one file to another file with the selected case sensitivity of the "same file" check.  \
+    ///
+    /// With CaseSensitivity::IgnoreCaseOnDrives `/mnt/c/Users/Foo` and `/mnt/c/users/foo` are the same file and nothing happens.
+    /// Without this check std::fs::copy on a case-insensitive drive would truncate the file.
+    pub fn copy_file_to_file_with(&self, destination_file: &CrossPathBuf, case_sensitivity: CaseSensitivity) -> Result<()> {
+        if !self.is_same_location(destination_file, case_sensitivity) {
             destination_file.create_dir_all_for_file()?;
-            std::fs::copy(self.to_path_buf_current_os(), destination_file.to_path_buf_current_os()).map_err(|err| Error::IoError {
+            std::fs::copy(self.checked_native_path()?, destination_file.checked_native_path()?).map_err(|err| Error::IoError {
                 source: (err),
                 path: (self.cross_path.clone()),
             })?;
@@ -666,22 +999,911 @@ impl CrossPathBuf {
 
     /// Renames a file or directory to a new name, replacing the original file if to already exists.  \
     pub fn rename_or_move(&self, destination_file: &CrossPathBuf) -> Result<()> {
-        if self.to_path_buf_current_os() != destination_file.to_path_buf_current_os() {
+        self.rename_or_move_with(destination_file, CaseSensitivity::Sensitive)
+    }
+
+    /// Renames a file or directory with the selected case sensitivity of the "same file" check.  \
+    ///
+    /// With CaseSensitivity::IgnoreCaseOnDrives `/mnt/c/Users/Foo` and `/mnt/c/users/foo` are the same file and nothing happens.
+    /// To change only the case of a name use CaseSensitivity::Sensitive.
+    pub fn rename_or_move_with(&self, destination_file: &CrossPathBuf, case_sensitivity: CaseSensitivity) -> Result<()> {
+        if !self.is_same_location(destination_file, case_sensitivity) {
             destination_file.create_dir_all_for_file()?;
-            std::fs::rename(self.to_path_buf_current_os(), destination_file.to_path_buf_current_os()).map_err(|err| Error::IoError {
+            std::fs::rename(self.checked_native_path()?, destination_file.checked_native_path()?).map_err(|err| Error::IoError {
                 source: (err),
                 path: (self.cross_path.clone()),
             })?;
         }
         Ok(())
     }
-}
 
-/// Method display() is used in format!("{}").
-impl std::fmt::Display for CrossPathBuf {
-    /// Method display() is used in format!("{}").
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        std::fmt::Display::fmt(self.as_str(), f)
+    /// The "same source and destination" check for copy and rename.
+    fn is_same_location(&self, other: &CrossPathBuf, case_sensitivity: CaseSensitivity) -> bool {
+        if self.native_path == other.native_path {
+            return true;
+        }
+        let is_on_drive = |cross_path: &CrossPathBuf| matches!(cross_path.components().next(), Some(CrossComponent::Drive(_)));
+        case_sensitivity == CaseSensitivity::IgnoreCaseOnDrives && is_on_drive(self) && is_on_drive(other) && self.eq_ignore_case(other)
+    }
+
+    /// Finds the entries in this directory whose names differ only by Unicode normalization.  \
+    ///
+    /// A name like `café` created on macOS is NFD and the same name typed on Linux is NFC.
+    /// Linux stores them as two different files, but they look the same and other OS would see one file.  \
+    /// Returns the groups of colliding names, every group has at least two names. Empty if there is no collision.
+    pub fn normalization_collisions(&self) -> Result<Vec<Vec<String>>> {
+        let entries = std::fs::read_dir(self.checked_native_path()?).map_err(|err| Error::IoError {
+            source: (err),
+            path: (self.cross_path.clone()),
+        })?;
+        let mut names_by_nfc: std::collections::BTreeMap<String, Vec<String>> = std::collections::BTreeMap::new();
+        for entry in entries {
+            let entry = entry.map_err(|err| Error::IoError {
+                source: (err),
+                path: (self.cross_path.clone()),
+            })?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            names_by_nfc.entry(name.nfc().collect()).or_default().push(name);
+        }
+        Ok(names_by_nfc
+            .into_values()
+            .filter(|names| names.len() > 1)
+            .map(|mut names| {
+                names.sort();
+                names
+            })
+            .collect())
+    }
+}
+
+impl CrossPath {
+    /// Wraps a str slice that is already in the Neutral Crossplatform format.  \
+    ///
+    /// Private, because the str is not validated.
+    fn from_valid_str(cross_path: &str) -> &CrossPath {
+        // SAFETY: CrossPath is repr(transparent) over str, so the pointer cast keeps the same layout and length metadata.
+        unsafe { &*(cross_path as *const str as *const CrossPath) }
+    }
+
+    /// Converts crossplatform path into Windows path.  \
+    ///
+    /// '~'     will be transformed into home  \
+    /// /mnt/c/ will be transformed into c:\\  \
+    /// /mnt/d/ will be transformed into d:\\  \
+    /// /tmp    will be transformed into %TEMP%  \
+    /// The home and temp folders are read from the environment.
+    pub fn to_path_buf_win(&self) -> std::path::PathBuf {
+        self.to_path_buf_with(&ResolveContext::from_env(TargetOs::Windows))
+    }
+
+    /// Converts crossplatform path into Linux path.  \
+    ///
+    /// '~'    will be transformed into home  \
+    /// The home folder is read from the environment.  \
+    /// The `%XX` are not decoded, because only a CrossPathBuf knows if it was escaped.
+    pub fn to_path_buf_nix(&self) -> std::path::PathBuf {
+        self.to_path_buf_with(&ResolveContext::from_env(TargetOs::Linux))
+    }
+
+    /// Converts crossplatform path into a path for the target OS of the context.  \
+    ///
+    /// The home folder, temp folder and drive mount prefix are taken from the context and not from the environment.  \
+    /// This makes the conversion deterministic and it can compute a path for a different machine.
+    pub fn to_path_buf_with(&self, ctx: &ResolveContext) -> std::path::PathBuf {
+        let (prefix, names) = self.native_parts_with(ctx);
+        match ctx.target_os {
+            TargetOs::Windows => std::path::PathBuf::from(add_extended_length_prefix(format!("{prefix}{names}"), ctx)),
+            TargetOs::Linux | TargetOs::MacOs => std::path::PathBuf::from(format!("{prefix}{names}")),
+        }
+    }
+
+    /// Converts crossplatform path into a path for the target OS of the context and decodes the escapes in the names.  \
+    ///
+    /// Used only for the escaped CrossPathBuf. The prefix replaced from the context is not decoded.
+    fn to_path_buf_unescaped_with(&self, ctx: &ResolveContext) -> std::path::PathBuf {
+        let (prefix, names) = self.native_parts_with(ctx);
+        match ctx.target_os {
+            // Windows names are utf16, so the bytes that are not utf8 cannot be restored anyway
+            TargetOs::Windows => {
+                let names = escape::unescape_to_string_lossy(&names, TargetOs::Windows);
+                std::path::PathBuf::from(add_extended_length_prefix(format!("{prefix}{names}"), ctx))
+            }
+            TargetOs::Linux | TargetOs::MacOs => escape::unescape_to_path_buf(&prefix, &names, ctx.target_os),
+        }
+    }
+
+    /// Renders the crossplatform path as a string for the target OS, independent of the host OS.  \
+    ///
+    /// It uses the native separators of the target: backslash for Windows and slash for Linux and MacOs.  \
+    /// Windows drives are written uppercase like `C:\`. UNC network shares are written like `\\server\share`.  \
+    /// The anchors `~` and `/tmp` are expanded from the context.  \
+    /// It does not touch the filesystem, so on Linux CI it can produce `C:\test\path` for a Windows installer.
+    pub fn to_string_for(&self, target_os: TargetOs, ctx: &ResolveContext) -> String {
+        self.render_for(target_os, ctx, false)
+    }
+
+    /// Renders the path for the target OS. With `unescape` the escapes in the names are decoded.
+    fn render_for(&self, target_os: TargetOs, ctx: &ResolveContext, unescape: bool) -> String {
+        // the user-defined mapping has priority over the built-in anchors
+        if let Some((native_prefix, rest)) = ctx.path_mapping.map_to_native(self, target_os) {
+            // the native prefix of the rule is never decoded
+            let rest = if unescape {
+                escape::unescape_to_string_lossy(&rest, target_os)
+            } else {
+                rest
+            };
+            let mapped = format!("{native_prefix}{rest}");
+            return match target_os {
+                TargetOs::Windows => add_extended_length_prefix(mapped, ctx),
+                TargetOs::Linux | TargetOs::MacOs => mapped,
+            };
+        }
+        let separator = match target_os {
+            TargetOs::Windows => '\\',
+            TargetOs::Linux | TargetOs::MacOs => '/',
+        };
+        // the folders from the context can be written with any separator
+        let native_separators = |dir: &str| -> String {
+            dir.replace(['/', '\\'], &separator.to_string())
+                .trim_end_matches(separator)
+                .to_string()
+        };
+        let mut rendered = String::new();
+        for component in self.components() {
+            match component {
+                CrossComponent::Root => rendered.push(separator),
+                CrossComponent::Home => match &ctx.home_dir {
+                    Some(home) => rendered.push_str(&native_separators(home)),
+                    None => rendered.push('~'),
+                },
+                CrossComponent::UserHome(user) => match ctx.user_home_dirs.get(user) {
+                    Some(home) => rendered.push_str(&native_separators(home)),
+                    None => rendered.push_str(&format!("~{user}")),
+                },
+                CrossComponent::Drive(letter) => match target_os {
+                    TargetOs::Windows => rendered.push_str(&format!("{}:\\", letter.to_ascii_uppercase())),
+                    TargetOs::Linux | TargetOs::MacOs => rendered.push_str(&format!("{}{letter}", ctx.drive_mount_style.prefix())),
+                },
+                CrossComponent::Temp => match target_os {
+                    TargetOs::Windows => rendered.push_str(&native_separators(&ctx.temp_dir)),
+                    TargetOs::Linux | TargetOs::MacOs => rendered.push_str("/tmp"),
+                },
+                CrossComponent::Unc { server, share } => match target_os {
+                    TargetOs::Windows => rendered.push_str(&format!(r#"\\{server}\{share}"#)),
+                    TargetOs::Linux | TargetOs::MacOs => rendered.push_str(&format!("{}{server}/{share}", ctx.unc_mount_prefix)),
+                },
+                CrossComponent::Normal(name) => {
+                    if !rendered.is_empty() && !rendered.ends_with(separator) {
+                        rendered.push(separator);
+                    }
+                    if unescape {
+                        rendered.push_str(&escape::unescape_to_string_lossy(name, target_os));
+                    } else {
+                        rendered.push_str(name);
+                    }
+                }
+            }
+        }
+        // the trailing slash of a folder is preserved
+        if self.cross_path.ends_with('/') && !rendered.ends_with(separator) {
+            rendered.push(separator);
+        }
+        match target_os {
+            TargetOs::Windows => add_extended_length_prefix(rendered, ctx),
+            TargetOs::Linux | TargetOs::MacOs => rendered,
+        }
+    }
+
+    /// Checks the length of the path for the current OS with the default length limits.  \
+    ///
+    /// See check_length_with().
+    pub fn check_length(&self) -> Result<()> {
+        self.check_length_with(&ResolveContext::default())
+    }
+
+    /// Checks the length of the path and of every name for the target OS of the context.  \
+    ///
+    /// The names are checked in bytes and in UTF-16 units. The whole path is checked after the expansion of `~` and `/tmp`,
+    /// in UTF-16 units for Windows and in bytes for Linux. The limits are from ctx.length_limits.  \
+    /// With ctx.extended_length_prefix the Windows limit is the much larger limit of the extended-length path.  \
+    /// Returns Error::TooLong with the length and the limit that was exceeded.
+    pub fn check_length_with(&self, ctx: &ResolveContext) -> Result<()> {
+        let limits = &ctx.length_limits;
+        let too_long = |length: usize, limit: usize| {
+            if length > limit {
+                Err(Error::TooLong {
+                    path: self.cross_path.to_string(),
+                    length,
+                    limit,
+                })
+            } else {
+                Ok(())
+            }
+        };
+        for component in self.components() {
+            if let CrossComponent::Normal(name) = component {
+                too_long(name.len(), limits.max_component_bytes)?;
+                too_long(name.encode_utf16().count(), limits.max_component_utf16)?;
+            }
+        }
+        // the limits include the terminating NULL
+        let rendered = self.to_string_for(ctx.target_os, ctx);
+        match ctx.target_os {
+            TargetOs::Windows if ctx.extended_length_prefix => {
+                too_long(rendered.encode_utf16().count() + 1, limits.max_path_windows_extended)
+            }
+            TargetOs::Windows => too_long(rendered.encode_utf16().count() + 1, limits.max_path_windows),
+            TargetOs::Linux | TargetOs::MacOs => too_long(rendered.len() + 1, limits.max_path_nix),
+        }
+    }
+
+    /// Splits the native path for the target OS of the context into the prefix from the context and the rest with the names.  \
+    ///
+    /// Only the names can contain the reversible escapes from from_path_escaped(). The prefix is never decoded.
+    fn native_parts_with(&self, ctx: &ResolveContext) -> (String, std::borrow::Cow<'_, str>) {
+        // the user-defined mapping has priority over the built-in anchors
+        if let Some((native_prefix, rest)) = ctx.path_mapping.map_to_native(self, ctx.target_os) {
+            return (native_prefix, std::borrow::Cow::Owned(rest));
+        }
+        let (anchor, names) = self.split_anchor();
+        let is_windows = ctx.target_os == TargetOs::Windows;
+        let native_anchor = match anchor {
+            // '~'    will be transformed into home
+            Some(CrossComponent::Home) => ctx.home_dir.clone(),
+            Some(CrossComponent::UserHome(user)) => ctx.user_home_dirs.get(user).cloned(),
+            // /mnt/c/ will be transformed into c:\\
+            Some(CrossComponent::Drive(letter)) if is_windows => Some(format!("{letter}:")),
+            // /mnt/c/ will be transformed with the drive mount style. The default /mnt/c is like in WSL.
+            Some(CrossComponent::Drive(letter)) => Some(format!("{}{letter}", ctx.drive_mount_style.prefix())),
+            // /tmp   will be transformed into %TEMP%
+            Some(CrossComponent::Temp) if is_windows => Some(ctx.temp_dir.clone()),
+            // //server/share will be transformed with the UNC mount prefix.
+            Some(CrossComponent::Unc { server, share }) if !is_windows => Some(format!("{}{server}/{share}", ctx.unc_mount_prefix)),
+            _ => None,
+        };
+        match native_anchor {
+            Some(native_anchor) => (native_anchor, std::borrow::Cow::Borrowed(names)),
+            None => (String::new(), std::borrow::Cow::Borrowed(&self.cross_path)),
+        }
+    }
+
+    /// Splits the path into the anchor and the rest with the names.  \
+    ///
+    /// The anchor is matched only as a whole leading component, so `/tmpdata` has the anchor Root and not Temp.
+    fn split_anchor(&self) -> (Option<CrossComponent<'_>>, &str) {
+        match (self.components().next(), self.iter().next()) {
+            (Some(CrossComponent::Normal(_)), _) | (None, _) | (_, None) => (None, &self.cross_path),
+            (Some(anchor), Some(anchor_text)) => (Some(anchor), &self.cross_path[anchor_text.len()..]),
+        }
+    }
+
+    /// Converts crossplatform path into current OS path.  \
+    ///
+    /// '~'     will be transformed into home  \
+    /// /mnt/c/ will be transformed into c:\\  \
+    /// /mnt/d/ will be transformed into d:\\  \
+    /// /tmp    will be transformed into %TEMP%  
+    pub fn to_path_buf_current_os(&self) -> std::path::PathBuf {
+        self.to_path_buf_with(ResolveContext::process())
+    }
+
+    /// Returns the crossplatform str for use in Display and store into config files.
+    pub fn as_str(&self) -> &str {
+        &self.cross_path
+    }
+
+    /// Compares two paths after the Unicode NFC normalization.  \
+    ///
+    /// The `café` from macOS in NFD and the `café` typed on Linux in NFC are equal.
+    /// The derived PartialEq compares the stored strings and treats them as different paths.
+    pub fn eq_normalized(&self, other: &CrossPath) -> bool {
+        self.cross_path.nfc().eq(other.cross_path.nfc())
+    }
+
+    /// Compares two paths case-insensitive with Unicode case folding.  \
+    ///
+    /// Windows and the default macOS volumes are case-insensitive, so `/mnt/c/Users/Foo` and `/mnt/c/users/foo` are the same location.
+    /// For keys in HashMap or BTreeMap use the CaseInsensitive wrapper.
+    pub fn eq_ignore_case(&self, other: &CrossPath) -> bool {
+        case_insensitive::case_fold(self) == case_insensitive::case_fold(other)
+    }
+
+    /// Returns `true` if the path points at an existing entity.  \
+    ///
+    /// A `~user` that is not known on this machine is never an existing entity.
+    pub fn exists(&self) -> bool {
+        self.to_cross_path_buf().exists()
+    }
+
+    /// Returns `true` if the path exists on disk and is pointing at a regular file.
+    pub fn is_file(&self) -> bool {
+        self.to_cross_path_buf().is_file()
+    }
+
+    /// Returns `true` if the path exists on disk and is pointing at a directory.
+    pub fn is_dir(&self) -> bool {
+        self.to_cross_path_buf().is_dir()
+    }
+
+    /// Joins two paths and returns a new CrossPathBuf to allow function chaining.  \
+    ///
+    /// It works differently from the original Rust join() where if the second path is absolute, it overwrites the first path.  \
+    /// Here the second path is always relative and is added to the first path.
+    pub fn join_relative(&self, str_path: &str) -> Result<CrossPathBuf> {
+        let second_path = CrossPathBuf::new(str_path)?;
+        let cross_path = format!(
+            "{}/{}",
+            self.cross_path.trim_end_matches("/"),
+            second_path.as_str().trim_start_matches("/")
+        );
+        Ok(CrossPathBuf::from_valid_string(cross_path))
+    }
+
+    /// Expands the environment variables in the path and returns a new CrossPathBuf.  \
+    ///
+    /// It understands the POSIX syntax `$VAR` and `${VAR}` and the Windows syntax `%VAR%`.  \
+    /// The values are read from the environment of the process.  \
+    /// The result is validated again with CrossPathBuf::new(), so values like `C:\Users\alice` are converted.  \
+    /// An unset variable returns Error::EnvVarNotSet with the variable name.
+    pub fn expand_env(&self) -> Result<CrossPathBuf> {
+        self.expand_env_with(|name| std::env::var(name).ok())
+    }
+
+    /// Expands the variables in the path with values from the lookup function and returns a new CrossPathBuf.  \
+    ///
+    /// The same as expand_env(), but the variable source is pluggable, so tests don't depend on the real environment.
+    pub fn expand_env_with(&self, lookup: impl Fn(&str) -> Option<String>) -> Result<CrossPathBuf> {
+        let expanded = env_expand::expand_variables(self.as_str(), &lookup, false)?;
+        CrossPathBuf::new(&expanded)
+    }
+
+    /// Returns a borrowed CrossPath without leading start slash (repeatedly removed).  
+    pub fn trim_start_slash(&self) -> Result<&CrossPath> {
+        Ok(CrossPath::from_valid_str(self.cross_path.trim_start_matches('/').trim()))
+    }
+
+    /// Returns a borrowed CrossPath without trailing end slash (repeatedly removed).  
+    pub fn trim_end_slash(&self) -> Result<&CrossPath> {
+        Ok(CrossPath::from_valid_str(self.cross_path.trim_end_matches('/').trim()))
+    }
+
+    /// Returns a CrossPathBuf with one leading start slash.  
+    pub fn add_start_slash(&self) -> Result<CrossPathBuf> {
+        let cross_path = format!("/{}", self.cross_path.trim_start_matches('/').trim());
+        Ok(CrossPathBuf::from_valid_string(cross_path))
+    }
+
+    /// Returns a CrossPathBuf with one trailing end slash.  
+    pub fn add_end_slash(&self) -> Result<CrossPathBuf> {
+        let cross_path = format!("{}/", self.cross_path.trim_end_matches('/').trim());
+        Ok(CrossPathBuf::from_valid_string(cross_path))
+    }
+
+    /// Returns the final component of the Path, if there is one.
+    ///
+    /// If the path is a normal file, this is the file name.
+    /// If it's the path of a directory, this is the directory name.
+    /// The anchors `/`, `~`, `/mnt/c` and `/tmp` don't have a file_name.
+    pub fn file_name(&self) -> Result<&str> {
+        match self.components().next_back() {
+            Some(CrossComponent::Normal(file_name)) => Ok(file_name),
+            _ => Err(Error::NoFileName(self.cross_path.to_string())),
+        }
+    }
+
+    /// Extracts the extension (without the leading dot), if possible.  \
+    ///
+    /// It is different from the std::fs extension() because  \
+    /// it returns an empty string if there is no extension.  \
+    /// It returns Error only if there is no file_name.  
+    pub fn extension(&self) -> Result<&str> {
+        // return error if there is no file_name
+        let file_name = self.file_name()?;
+        // return empty string if there is no extension
+        Ok(split_file_name_at_dot(file_name).1.unwrap_or(""))
+    }
+
+    /// Extracts the stem (non-extension) portion of file_name (the final component of the Path).
+    pub fn file_stem(&self) -> Result<&str> {
+        Ok(split_file_name_at_dot(self.file_name()?).0)
+    }
+
+    /// Returns the Path without its final component, if there is one.  \
+    ///
+    /// The parent is a borrowed sub-slice of this path. No allocation and no reparsing.  \
+    /// The anchors `/`, `~`, `/mnt/c` and `/tmp` don't have a parent.
+    pub fn parent(&self) -> Result<&CrossPath> {
+        let mut components = self.components();
+        match components.next_back() {
+            Some(CrossComponent::Normal(_file_name)) => Ok(components.as_cross_path()),
+            _ => Err(Error::NoParent(self.cross_path.to_string())),
+        }
+    }
+
+    /// Returns an iterator over the components of the path.  \
+    ///
+    /// The first component can be an anchor: Root `/`, Home `~`, UserHome `~user`, Drive `/mnt/c` or Temp `/tmp`.  \
+    /// The anchor is recognized only as a whole component, so `/tmpdata` is Root and Normal("tmpdata").
+    pub fn components(&self) -> Components<'_> {
+        Components::new(self)
+    }
+
+    /// Returns an iterator over the components of the path as str slices.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter::new(self)
+    }
+
+    /// Returns an iterator over the path and all its ancestors.  \
+    ///
+    /// The iterator ends with the anchor or with the empty path for relative paths.
+    pub fn ancestors(&self) -> Ancestors<'_> {
+        Ancestors::new(self)
+    }
+
+    /// Returns `true` if the base is a prefix of this path, compared component-wise.  \
+    ///
+    /// `/home/user2` does not start with `/home/user`. The anchors are compared as whole components,
+    /// so `~/a` does not start with `/home/user` even if that is the home folder. See expand_anchors_with().  \
+    /// The comparison is case-sensitive. The trailing slash is ignored.
+    pub fn starts_with(&self, base: &CrossPath) -> bool {
+        self.strip_prefix(base).is_ok()
+    }
+
+    /// Returns `true` if the child is a suffix of this path, compared component-wise.  \
+    ///
+    /// `/home/user/project/src` ends with `project/src`, but not with `ject/src`.
+    pub fn ends_with(&self, child: &CrossPath) -> bool {
+        let mut components = self.components();
+        child
+            .components()
+            .rev()
+            .all(|child_component| components.next_back() == Some(child_component))
+    }
+
+    /// Returns the rest of the path after the base, as a borrowed sub-slice of this path.  \
+    ///
+    /// The base must be a prefix like in starts_with(), else it returns Error::NotUnderBase.
+    ///
+    /// ```rust
+    /// use crossplatform_path::CrossPathBuf;
+    ///
+    /// let cross_path = CrossPathBuf::new("~/project/src/lib.rs")?;
+    /// assert_eq!(cross_path.strip_prefix(&CrossPathBuf::new("~/project")?)?, "src/lib.rs");
+    /// assert!(cross_path.strip_prefix(&CrossPathBuf::new("/home/user/project")?).is_err());
+    /// # Ok::<(), crossplatform_path::Error>(())
+    /// ```
+    pub fn strip_prefix(&self, base: &CrossPath) -> Result<&CrossPath> {
+        let mut components = self.components();
+        for base_component in base.components() {
+            if components.next() != Some(base_component) {
+                return Err(Error::NotUnderBase {
+                    path: self.cross_path.to_string(),
+                    base: base.cross_path.to_string(),
+                });
+            }
+        }
+        Ok(components.as_cross_path())
+    }
+
+    /// Returns this path relative to the base. The path must be inside the base.  \
+    ///
+    /// The same as strip_prefix(), but it returns an owned CrossPathBuf.
+    /// To get a result with `../` use relative_to_with() and ParentTraversal::Allow.
+    pub fn relative_to(&self, base: &CrossPath) -> Result<CrossPathBuf> {
+        self.relative_to_with(base, ParentTraversal::Forbid)
+    }
+
+    /// Returns this path relative to the base, optionally climbing out of the base with `../`.  \
+    ///
+    /// The result with `../` is valid with DotComponents::ResolveKeepLeading, but not with new().  \
+    /// Paths with different anchors like `~` and `/mnt/c` have no relative path and return Error::NotUnderBase.
+    ///
+    /// ```rust
+    /// use crossplatform_path::{CrossPathBuf, ParentTraversal};
+    ///
+    /// let cross_path = CrossPathBuf::new("/mnt/c/project/assets/logo.png")?;
+    /// let base = CrossPathBuf::new("/mnt/c/project/src")?;
+    /// assert!(cross_path.relative_to(&base).is_err());
+    /// assert_eq!(cross_path.relative_to_with(&base, ParentTraversal::Allow)?, "../assets/logo.png");
+    /// # Ok::<(), crossplatform_path::Error>(())
+    /// ```
+    pub fn relative_to_with(&self, base: &CrossPath, parent_traversal: ParentTraversal) -> Result<CrossPathBuf> {
+        match relative::relative_path(self, base, parent_traversal) {
+            Some(relative_path) => Ok(CrossPathBuf::from_valid_string(relative_path)),
+            None => Err(Error::NotUnderBase {
+                path: self.cross_path.to_string(),
+                base: base.cross_path.to_string(),
+            }),
+        }
+    }
+
+    /// Replaces the anchors `~`, `~user` and `/tmp` with the home and temp folders from the context.  \
+    ///
+    /// The folders are converted into the neutral format, so `C:\Users\alice` becomes `/mnt/c/Users/alice`.  \
+    /// Then `~/a` can be compared with `/home/alice/a` or `/mnt/c/Users/alice/a` for that machine.
+    /// If the context has no home folder, the `~` stays unchanged.
+    /// A `~user` that is not in the user_home_dirs of the context returns Error::UnknownUser.
+    ///
+    /// ```rust
+    /// use crossplatform_path::{CrossPathBuf, ResolveContext, TargetOs};
+    ///
+    /// let ctx = ResolveContext {
+    ///     home_dir: Some("/home/alice".to_string()),
+    ///     ..ResolveContext::from_env(TargetOs::Linux)
+    /// };
+    /// let cross_path = CrossPathBuf::new("~/project/src")?;
+    /// let base = CrossPathBuf::new("/home/alice/project")?;
+    /// assert!(!cross_path.starts_with(&base));
+    /// assert!(cross_path.expand_anchors_with(&ctx)?.starts_with(&base));
+    /// # Ok::<(), crossplatform_path::Error>(())
+    /// ```
+    pub fn expand_anchors_with(&self, ctx: &ResolveContext) -> Result<CrossPathBuf> {
+        let (anchor, names) = self.split_anchor();
+        let dir = match anchor {
+            Some(CrossComponent::Home) => ctx.home_dir.as_deref(),
+            Some(CrossComponent::UserHome(user)) => match ctx.user_home_dirs.get(user) {
+                Some(user_home) => Some(user_home.as_str()),
+                None => return Err(Error::UnknownUser(user.to_string())),
+            },
+            Some(CrossComponent::Temp) => Some(ctx.temp_dir.as_str()),
+            _ => None,
+        };
+        let Some(dir) = dir else {
+            return Ok(self.to_cross_path_buf());
+        };
+        let expanded = format!("{}{names}", dir.trim_end_matches(['/', '\\']));
+        // the home folder can be the root `/`
+        CrossPathBuf::new(if expanded.is_empty() { "/" } else { &expanded })
+    }
+
+    /// Returns new object where the extension is replaced.
+    ///
+    /// If the extension did not exist, it is added.
+    pub fn replace_extension(&self, extension: &str) -> Result<CrossPathBuf> {
+        let old_extension = self.extension()?;
+        let dot_separator = if extension.is_empty() { "" } else { "." };
+        let cross_path = format!(
+            "{}{dot_separator}{extension}",
+            self.cross_path.trim_end_matches(old_extension).trim_end_matches(".")
+        );
+        CrossPathBuf::new(&cross_path)
+    }
+
+    /// Shorten the crossplatform path to avoid word-wrap for longer paths.
+    pub fn short_string(&self, max_char: u16) -> Result<String> {
+        /// It is used for substring, because string slice are counted in bytes and not chars.  \
+        ///
+        /// If we have multi-byte unicode characters we can get an error if the boundary is not on char boundary.  
+        fn byte_pos_from_chars(text: &str, char_pos: usize) -> Result<usize> {
+            Ok(text
+                .char_indices()
+                .nth(char_pos)
+                .ok_or_else(|| Error::NoFileName(text.to_string()))?
+                .0)
+        }
+        if self.cross_path.chars().count() > max_char as usize {
+            let half_in_char = (max_char / 2 - 2) as usize;
+            let pos1_in_bytes = byte_pos_from_chars(&self.cross_path, half_in_char)?;
+            let pos2_in_bytes = byte_pos_from_chars(&self.cross_path, self.cross_path.chars().count() - half_in_char)?;
+            Ok(format!(
+                "{}...{}",
+                &self.cross_path[..pos1_in_bytes],
+                &self.cross_path[pos2_in_bytes..]
+            ))
+        } else {
+            Ok(self.cross_path.to_string())
+        }
+    }
+
+    /// Creates an owned CrossPathBuf with the same content.
+    pub fn to_cross_path_buf(&self) -> CrossPathBuf {
+        CrossPathBuf::from_valid_string(self.cross_path.to_string())
+    }
+}
+
+/// Returns the rest of the path after the folder for from_path_neutralized().  \
+///
+/// The names are compared case-insensitive if ignore_case, because the Windows drives are case-insensitive.
+fn strip_folder<'a>(cross_path: &'a CrossPath, folder: &CrossPath, ignore_case: bool) -> Option<&'a CrossPath> {
+    let mut names = cross_path.iter();
+    let is_prefix = folder.iter().all(|folder_name| {
+        names.next().is_some_and(|name| {
+            name == folder_name || (ignore_case && CrossPath::from_valid_str(name).eq_ignore_case(CrossPath::from_valid_str(folder_name)))
+        })
+    });
+    is_prefix.then(|| names.as_cross_path())
+}
+
+/// Removes `.` and resolves `..` against the preceding names.  \
+///
+/// The leading `..` of a relative path are kept. The validation already checked that `..` does not climb above the anchor.
+fn resolve_dot_components(cross_path: &str) -> String {
+    let path = CrossPath::from_valid_str(cross_path);
+    let mut anchor = "";
+    let mut names: Vec<&str> = vec![];
+    for (component, text) in path.components().zip(path.iter()) {
+        match component {
+            CrossComponent::Normal(".") => (),
+            CrossComponent::Normal("..") if names.last().is_some_and(|name| *name != "..") => {
+                names.pop();
+            }
+            CrossComponent::Normal(name) => names.push(name),
+            _anchor => anchor = text,
+        }
+    }
+    let mut resolved = anchor.to_string();
+    if !names.is_empty() {
+        if !resolved.is_empty() && !resolved.ends_with('/') {
+            resolved.push('/');
+        }
+        resolved.push_str(&names.join("/"));
+        // the trailing slash of a folder is preserved
+        if cross_path.ends_with('/') {
+            resolved.push('/');
+        }
+    }
+    resolved
+}
+
+/// Adds the Windows extended-length prefix `\\?\` if the context allows it and the path is longer than MAX_PATH.  \
+///
+/// Only absolute paths with a drive or UNC can have the prefix. The extended-length path must use backslashes.
+fn add_extended_length_prefix(win_path: String, ctx: &ResolveContext) -> String {
+    // MAX_PATH includes the terminating NULL
+    if !ctx.extended_length_prefix || win_path.encode_utf16().count() < ctx.length_limits.max_path_windows {
+        return win_path;
+    }
+    let backslash_path = win_path.replace('/', r#"\"#);
+    let mut chars = backslash_path.chars();
+    if let Some(unc_path) = backslash_path.strip_prefix(r#"\\"#) {
+        format!(r#"\\?\UNC\{unc_path}"#)
+    } else if chars.next().is_some_and(|c| c.is_ascii_alphabetic()) && chars.as_str().starts_with(r#":\"#) {
+        format!(r#"\\?\{backslash_path}"#)
+    } else {
+        win_path
+    }
+}
+
+/// Removes the Windows extended-length prefix `\\?\` from the start of the path.  \
+///
+/// `\\?\C:\path` becomes `C:\path` and `\\?\UNC\server\share` becomes `\\server\share`.
+fn strip_extended_length_prefix(str_path: &str) -> std::borrow::Cow<'_, str> {
+    let trimmed = str_path.trim_start();
+    for prefix in [r#"\\?\"#, "//?/"] {
+        if let Some(rest) = trimmed.strip_prefix(prefix) {
+            if let Some(unc_path) = rest.strip_prefix(r#"UNC\"#).or_else(|| rest.strip_prefix("UNC/")) {
+                return std::borrow::Cow::Owned(format!(r#"\\{unc_path}"#));
+            }
+            return std::borrow::Cow::Borrowed(rest);
+        }
+    }
+    std::borrow::Cow::Borrowed(str_path)
+}
+
+/// Splits the file_name into stem and extension the same way as std::path does.  \
+///
+/// A leading dot does not start an extension, so `.bashrc` has no extension.
+fn split_file_name_at_dot(file_name: &str) -> (&str, Option<&str>) {
+    match file_name.rsplit_once('.') {
+        None | Some(("", _)) => (file_name, None),
+        Some((stem, extension)) => (stem, Some(extension)),
+    }
+}
+
+/// Method display() is used in format!("{}").
+impl std::fmt::Display for CrossPathBuf {
+    /// Method display() is used in format!("{}").
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+/// Method display() is used in format!("{}").
+impl std::fmt::Display for CrossPath {
+    /// Method display() is used in format!("{}").
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+/// Debug looks the same as the derived Debug of CrossPathBuf.
+impl std::fmt::Debug for CrossPath {
+    /// Debug looks the same as the derived Debug of CrossPathBuf.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("CrossPath").field("cross_path", &&self.cross_path).finish()
+    }
+}
+
+/// CrossPathBuf derefs to CrossPath, like PathBuf derefs to Path.
+impl std::ops::Deref for CrossPathBuf {
+    type Target = CrossPath;
+
+    /// CrossPathBuf derefs to CrossPath, like PathBuf derefs to Path.
+    fn deref(&self) -> &CrossPath {
+        self.as_cross_path()
+    }
+}
+
+/// Borrow is needed for ToOwned and Cow<CrossPath>.
+impl std::borrow::Borrow<CrossPath> for CrossPathBuf {
+    /// Borrow is needed for ToOwned and Cow<CrossPath>.
+    fn borrow(&self) -> &CrossPath {
+        self.as_cross_path()
+    }
+}
+
+/// ToOwned makes Cow<CrossPath> possible.
+impl ToOwned for CrossPath {
+    type Owned = CrossPathBuf;
+
+    /// ToOwned makes Cow<CrossPath> possible.
+    fn to_owned(&self) -> CrossPathBuf {
+        self.to_cross_path_buf()
+    }
+}
+
+/// Functions can accept `impl AsRef<CrossPath>` for both types.
+impl AsRef<CrossPath> for CrossPath {
+    /// Functions can accept `impl AsRef<CrossPath>` for both types.
+    fn as_ref(&self) -> &CrossPath {
+        self
+    }
+}
+
+/// Functions can accept `impl AsRef<CrossPath>` for both types.
+impl AsRef<CrossPath> for CrossPathBuf {
+    /// Functions can accept `impl AsRef<CrossPath>` for both types.
+    fn as_ref(&self) -> &CrossPath {
+        self.as_cross_path()
+    }
+}
+
+/// Equality is on the neutral crossplatform string.
+impl PartialEq for CrossPath {
+    /// Equality is on the neutral crossplatform string.
+    fn eq(&self, other: &CrossPath) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+/// Equality is on the neutral crossplatform string and the escaped flag.  \
+///
+/// The cached native path is derived from them, so `new(x)` and `new_escaped(x)` are different locations.
+impl PartialEq for CrossPathBuf {
+    /// Equality is on the neutral crossplatform string and the escaped flag.
+    fn eq(&self, other: &CrossPathBuf) -> bool {
+        self.as_str() == other.as_str() && self.escaped == other.escaped
+    }
+}
+
+/// Compare the borrowed and the owned type.
+impl PartialEq<CrossPathBuf> for CrossPath {
+    /// Compare the borrowed and the owned type.
+    fn eq(&self, other: &CrossPathBuf) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+/// Compare the owned and the borrowed type.
+impl PartialEq<CrossPath> for CrossPathBuf {
+    /// Compare the owned and the borrowed type.
+    fn eq(&self, other: &CrossPath) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+/// Compare with a str in the neutral crossplatform format.
+impl PartialEq<str> for CrossPath {
+    /// Compare with a str in the neutral crossplatform format.
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+/// Compare with a str in the neutral crossplatform format.
+impl PartialEq<str> for CrossPathBuf {
+    /// Compare with a str in the neutral crossplatform format.
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+/// Compare with a str in the neutral crossplatform format.
+impl PartialEq<&str> for CrossPathBuf {
+    /// Compare with a str in the neutral crossplatform format.
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+/// Equality is on the neutral crossplatform string, so it can be a key in HashMap.
+impl Eq for CrossPath {}
+
+/// Equality is on the neutral crossplatform string and the escaped flag, so it can be a key in HashMap.
+impl Eq for CrossPathBuf {}
+
+/// Hash is on the neutral crossplatform string, the same for the owned and the borrowed type.
+impl std::hash::Hash for CrossPath {
+    /// Hash is on the neutral crossplatform string, the same for the owned and the borrowed type.
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+/// Hash is on the neutral crossplatform string and the escaped flag.  \
+///
+/// A path that is not escaped has the same hash as the borrowed CrossPath, so a HashMap can be searched with `&CrossPath`.
+/// The escaped paths are a different location and they are not found with `&CrossPath`.
+impl std::hash::Hash for CrossPathBuf {
+    /// Hash is on the neutral crossplatform string and the escaped flag.
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.as_cross_path().hash(state);
+        if self.escaped {
+            self.escaped.hash(state);
+        }
+    }
+}
+
+/// Ordering is component-wise and not on the raw string, so it can be a key in BTreeMap.
+impl Ord for CrossPath {
+    /// Ordering is component-wise and not on the raw string.
+    /// The anchors sort before all the names, so "/b" < "a" and "a/b" < "a-b".  \
+    /// If the components are equal (e.g. "a/" and "a"), the raw string decides, so the ordering is consistent with Eq.
+    fn cmp(&self, other: &CrossPath) -> std::cmp::Ordering {
+        self.components()
+            .cmp(other.components())
+            .then_with(|| self.as_str().cmp(other.as_str()))
+    }
+}
+
+/// Ordering is component-wise and not on the raw string.
+impl PartialOrd for CrossPath {
+    /// Ordering is component-wise and not on the raw string.
+    fn partial_cmp(&self, other: &CrossPath) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Ordering is component-wise and not on the raw string, so it can be a key in BTreeMap.
+impl Ord for CrossPathBuf {
+    /// Ordering is component-wise and not on the raw string.
+    /// On equal strings the path that is not escaped is first, so the ordering is consistent with Eq.
+    fn cmp(&self, other: &CrossPathBuf) -> std::cmp::Ordering {
+        self.as_cross_path()
+            .cmp(other.as_cross_path())
+            .then_with(|| self.escaped.cmp(&other.escaped))
+    }
+}
+
+/// Ordering is component-wise and not on the raw string.
+impl PartialOrd for CrossPathBuf {
+    /// Ordering is component-wise and not on the raw string.
+    fn partial_cmp(&self, other: &CrossPathBuf) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Creates an owned copy from the borrowed CrossPath.
+impl From<&CrossPath> for CrossPathBuf {
+    /// Creates an owned copy from the borrowed CrossPath.
+    fn from(cross_path: &CrossPath) -> Self {
+        cross_path.to_cross_path_buf()
+    }
+}
+
+/// Cow<CrossPath> from the borrowed CrossPath.
+impl<'a> From<&'a CrossPath> for std::borrow::Cow<'a, CrossPath> {
+    /// Cow<CrossPath> from the borrowed CrossPath.
+    fn from(cross_path: &'a CrossPath) -> Self {
+        std::borrow::Cow::Borrowed(cross_path)
+    }
+}
+
+/// Cow<CrossPath> from the owned CrossPathBuf.
+impl From<CrossPathBuf> for std::borrow::Cow<'_, CrossPath> {
+    /// Cow<CrossPath> from the owned CrossPathBuf.
+    fn from(cross_path: CrossPathBuf) -> Self {
+        std::borrow::Cow::Owned(cross_path)
     }
 }
 
@@ -689,18 +1911,154 @@ impl std::fmt::Display for CrossPathBuf {
 impl From<CrossPathBuf> for std::path::PathBuf {
     /// CrossPathBuf from() and into() are useful in places where PathBuf is needed.
     fn from(cross_path: CrossPathBuf) -> Self {
-        cross_path.to_path_buf_current_os()
+        cross_path.native_path
     }
 }
 
-// TODO: is it possible to impl AsRef<Path>?
-// many functions accept AsRef<Path>
-// problem: I cannot create a PathBuf inside as_ref() because
-// then cannot return value referencing temporary value
-/* impl AsRef<std::path::Path> for CrossPathBuf {
-    #[inline]
+/// Many functions in std::fs, tar and std::process::Command accept AsRef<Path>.
+impl AsRef<std::path::Path> for CrossPathBuf {
+    /// Returns the cached path for the current OS.
     fn as_ref(&self) -> &std::path::Path {
-        self.to_current_os_path_buf().as_path()
+        self.as_native_path()
+    }
+}
+
+/// Functions like std::process::Command::arg() accept AsRef<OsStr>.
+impl AsRef<std::ffi::OsStr> for CrossPathBuf {
+    /// Returns the cached path for the current OS.
+    fn as_ref(&self) -> &std::ffi::OsStr {
+        self.as_native_path().as_os_str()
+    }
+}
+
+/// Returns the neutral crossplatform str.
+impl AsRef<str> for CrossPath {
+    /// Returns the neutral crossplatform str.
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+/// Returns the neutral crossplatform str.
+impl AsRef<str> for CrossPathBuf {
+    /// Returns the neutral crossplatform str.
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+/// The str is validated with CrossPathBuf::new(), so it can be used with str::parse().
+impl std::str::FromStr for CrossPathBuf {
+    type Err = Error;
+
+    /// The str is validated with CrossPathBuf::new().
+    fn from_str(str_path: &str) -> Result<Self> {
+        Self::new(str_path)
+    }
+}
+
+/// The str is validated with CrossPathBuf::new().
+impl TryFrom<&str> for CrossPathBuf {
+    type Error = Error;
+
+    /// The str is validated with CrossPathBuf::new().
+    fn try_from(str_path: &str) -> Result<Self> {
+        Self::new(str_path)
+    }
+}
+
+/// The String is validated with CrossPathBuf::new().
+impl TryFrom<String> for CrossPathBuf {
+    type Error = Error;
+
+    /// The String is validated with CrossPathBuf::new().
+    fn try_from(str_path: String) -> Result<Self> {
+        Self::new(&str_path)
+    }
+}
+
+/// The Path is validated with CrossPathBuf::from_path().
+impl TryFrom<&std::path::Path> for CrossPathBuf {
+    type Error = Error;
+
+    /// The Path is validated with CrossPathBuf::from_path().
+    fn try_from(path: &std::path::Path) -> Result<Self> {
+        Self::from_path(path)
+    }
+}
+
+/// The PathBuf is validated with CrossPathBuf::from_path().
+impl TryFrom<std::path::PathBuf> for CrossPathBuf {
+    type Error = Error;
+
+    /// The PathBuf is validated with CrossPathBuf::from_path().
+    fn try_from(path: std::path::PathBuf) -> Result<Self> {
+        Self::from_path(&path)
+    }
+}
+
+/// Extend appends every path as relative, like join_relative(). The `..` cannot climb above the start of this path.
+impl<'a> Extend<&'a CrossPath> for CrossPathBuf {
+    /// Extend appends every path as relative, like join_relative(). The `..` cannot climb above the start of this path.
+    fn extend<I: IntoIterator<Item = &'a CrossPath>>(&mut self, iter: I) {
+        for cross_path in iter {
+            self.push_relative(cross_path);
+        }
+    }
+}
+
+/// Extend appends every path as relative, like join_relative(). The `..` cannot climb above the start of this path.
+impl Extend<CrossPathBuf> for CrossPathBuf {
+    /// Extend appends every path as relative, like join_relative(). The `..` cannot climb above the start of this path.
+    fn extend<I: IntoIterator<Item = CrossPathBuf>>(&mut self, iter: I) {
+        for cross_path in iter {
+            self.push_relative(&cross_path);
+        }
+    }
+}
+
+/// Collects the components into one path. The first one keeps its leading slash.  \
+///
+/// To collect from strings use `.map(CrossPathBuf::new).collect::<Result<CrossPathBuf>>()`.
+impl<'a> FromIterator<&'a CrossPath> for CrossPathBuf {
+    /// Collects the components into one path. The first one keeps its leading slash.
+    fn from_iter<I: IntoIterator<Item = &'a CrossPath>>(iter: I) -> Self {
+        let mut cross_path_buf = CrossPathBuf::from_valid_string(String::new());
+        cross_path_buf.extend(iter);
+        cross_path_buf
+    }
+}
+
+/// Collects the components into one path. The first one keeps its leading slash.  \
+///
+/// To collect from strings use `.map(CrossPathBuf::new).collect::<Result<CrossPathBuf>>()`.
+impl FromIterator<CrossPathBuf> for CrossPathBuf {
+    /// Collects the components into one path. The first one keeps its leading slash.
+    fn from_iter<I: IntoIterator<Item = CrossPathBuf>>(iter: I) -> Self {
+        let mut cross_path_buf = CrossPathBuf::from_valid_string(String::new());
+        cross_path_buf.extend(iter);
+        cross_path_buf
+    }
+}
+
+/// Iterates over the components as str slices.
+impl<'a> IntoIterator for &'a CrossPath {
+    type Item = &'a str;
+    type IntoIter = Iter<'a>;
+
+    /// Iterates over the components as str slices.
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterates over the components as str slices.
+impl<'a> IntoIterator for &'a CrossPathBuf {
+    type Item = &'a str;
+    type IntoIter = Iter<'a>;
+
+    /// Iterates over the components as str slices.
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
     }
 }
- */
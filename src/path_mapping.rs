@@ -0,0 +1,217 @@
+// crossplatform_path/src/path_mapping.rs
+
+//! User-defined prefix mapping between the neutral crossplatform path and the native path.
+//!
+//! The built-in anchors cannot express that a workstation maps `/data/shared` to `S:\` on Windows
+//! or that a Docker container mounts the repository at `/workspace`.
+//! The mapping rules are ordered and component-aware. The longest matching prefix wins.
+
+use crate::{CrossComponent, CrossPath, CrossPathBuf, Error, Result, TargetOs};
+
+/// The table used by ResolveContext::from_env(). It is set once, by set_process_default() or empty at the first use.
+static PROCESS_DEFAULT: std::sync::OnceLock<std::sync::Arc<PathMapping>> = std::sync::OnceLock::new();
+
+/// One rule that maps a crossplatform prefix to a native prefix for one target OS.  \
+///
+/// Created with PathMapping::add_rule() or PathMapping::from_config_str().
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MappingRule {
+    /// The rule is used only for this target OS.
+    target_os: TargetOs,
+    /// The absolute crossplatform prefix like `/data/shared` or `~/repo`.
+    cross_prefix: CrossPathBuf,
+    /// The native prefix like `S:\` or `/workspace`, as it is written for the target OS.
+    native_prefix: String,
+    /// The native prefix in the neutral format, used to match the native paths in from_path_neutralized().
+    neutral_native_prefix: CrossPathBuf,
+}
+
+impl MappingRule {
+    /// Returns the target OS of the rule.
+    pub fn target_os(&self) -> TargetOs {
+        self.target_os
+    }
+
+    /// Returns the crossplatform prefix.
+    pub fn cross_prefix(&self) -> &CrossPath {
+        &self.cross_prefix
+    }
+
+    /// Returns the native prefix as it is written for the target OS.
+    pub fn native_prefix(&self) -> &str {
+        &self.native_prefix
+    }
+}
+
+/// Ordered table of prefix mapping rules per target OS.  \
+///
+/// It is used in ResolveContext by to_path_buf_with(), to_string_for() and from_path_neutralized().  \
+/// The table set once with set_process_default() is used by ResolveContext::from_env(), so also by the native path of CrossPathBuf,
+/// to_path_buf_current_os(), the file operations and from_path().  \
+/// The prefixes are compared component-wise, so `/data/shared` does not match `/data/shared2`.
+/// If more rules match, the rule with the longest prefix wins. On equal length the first rule wins.  \
+/// The config file has one rule per line. Empty lines and lines starting with `#` are ignored:
+///
+/// ```text
+/// # target_os  crossplatform prefix  =>  native prefix
+/// windows  /data/shared  =>  S:\
+/// linux    ~/repo        =>  /workspace
+/// ```
+///
+/// ```rust
+/// use crossplatform_path::{CrossPathBuf, PathMapping, ResolveContext, TargetOs};
+///
+/// let path_mapping = PathMapping::from_config_str(r#"windows /data/shared => S:\"#)?;
+/// let ctx = ResolveContext {
+///     path_mapping: path_mapping.into(),
+///     ..ResolveContext::from_env(TargetOs::Windows)
+/// };
+/// let cross_path = CrossPathBuf::new("/data/shared/projects/report.txt")?;
+/// assert_eq!(cross_path.to_string_for(TargetOs::Windows, &ctx), r#"S:\projects\report.txt"#);
+/// # Ok::<(), crossplatform_path::Error>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PathMapping {
+    /// The rules in the order they were added.
+    rules: Vec<MappingRule>,
+}
+
+impl PathMapping {
+    /// Creates an empty table. No path is mapped.
+    pub const fn new() -> Self {
+        PathMapping { rules: Vec::new() }
+    }
+
+    /// Sets the table used by ResolveContext::from_env() in the whole process.  \
+    ///
+    /// It can be set only once, at startup before the first path is created, so the native paths do not depend on when they were created.  \
+    /// Returns Error::ProcessDefaultAlreadySet if it was already set or the empty default was already used.
+    pub fn set_process_default(path_mapping: PathMapping) -> Result<()> {
+        PROCESS_DEFAULT
+            .set(std::sync::Arc::new(path_mapping))
+            .map_err(|_path_mapping| Error::ProcessDefaultAlreadySet)
+    }
+
+    /// Returns the shared table set with set_process_default(). Empty if it was not set before the first use.
+    pub fn process_default() -> std::sync::Arc<PathMapping> {
+        std::sync::Arc::clone(PROCESS_DEFAULT.get_or_init(|| std::sync::Arc::new(PathMapping::new())))
+    }
+
+    /// Adds a rule at the end of the table.  \
+    ///
+    /// Both prefixes must be absolute. The native prefix is written for the target OS like `S:\`, `\\nas\share` or `/workspace`.  \
+    /// Returns Error::InvalidMapping if a prefix is relative.
+    pub fn add_rule(&mut self, target_os: TargetOs, cross_prefix: &str, native_prefix: &str) -> Result<()> {
+        let invalid_mapping = || Error::InvalidMapping(format!("{cross_prefix} => {native_prefix}"));
+        let cross_prefix_buf = CrossPathBuf::new(cross_prefix)?;
+        let neutral_native_prefix = CrossPathBuf::new(native_prefix)?;
+        if !is_absolute(&cross_prefix_buf) || !is_absolute(&neutral_native_prefix) {
+            return Err(invalid_mapping());
+        }
+        self.rules.push(MappingRule {
+            target_os,
+            cross_prefix: cross_prefix_buf,
+            native_prefix: native_prefix.to_string(),
+            neutral_native_prefix,
+        });
+        Ok(())
+    }
+
+    /// Parses the rules from the text of a config file.  \
+    ///
+    /// Every rule is `target_os crossplatform_prefix => native_prefix`. The target_os is `windows`, `linux` or `macos`.  \
+    /// Returns Error::InvalidMapping with the line number for a line that is not a valid rule.
+    pub fn from_config_str(text: &str) -> Result<Self> {
+        let mut path_mapping = PathMapping::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid_mapping = || Error::InvalidMapping(format!("on line {}: {line}", index + 1));
+            let (target_os, rule) = line.split_once(char::is_whitespace).ok_or_else(invalid_mapping)?;
+            let target_os = match target_os.to_ascii_lowercase().as_str() {
+                "windows" => TargetOs::Windows,
+                "linux" => TargetOs::Linux,
+                "macos" => TargetOs::MacOs,
+                _ => return Err(invalid_mapping()),
+            };
+            let (cross_prefix, native_prefix) = rule.split_once("=>").ok_or_else(invalid_mapping)?;
+            path_mapping
+                .add_rule(target_os, cross_prefix.trim(), native_prefix.trim())
+                .map_err(|_err| invalid_mapping())?;
+        }
+        Ok(path_mapping)
+    }
+
+    /// Reads and parses the config file with the rules.
+    pub fn from_config_file(config_file: &CrossPathBuf) -> Result<Self> {
+        Self::from_config_str(&config_file.read_to_string()?)
+    }
+
+    /// Returns the rules in the order they were added.
+    pub fn rules(&self) -> &[MappingRule] {
+        &self.rules
+    }
+
+    /// Returns `true` if the table has no rules.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Maps the crossplatform path into the native prefix and the rest of the path for the target OS, if a rule matches.  \
+    ///
+    /// The rest of the path after the prefix is written with the native separators.
+    /// They are returned separately, because only the rest can contain the escapes from from_path_escaped().
+    pub(crate) fn map_to_native(&self, cross_path: &CrossPath, target_os: TargetOs) -> Option<(String, String)> {
+        let (rule, rest) = longest_match(self.rules.iter().filter(|rule| rule.target_os == target_os).filter_map(|rule| {
+            Some((
+                rule,
+                rule.cross_prefix.components().count(),
+                cross_path.strip_prefix(&rule.cross_prefix).ok()?,
+            ))
+        }))?;
+        if rest.as_str().is_empty() {
+            return Some((rule.native_prefix.clone(), String::new()));
+        }
+        let separator = match target_os {
+            TargetOs::Windows => "\\",
+            TargetOs::Linux | TargetOs::MacOs => "/",
+        };
+        let native_prefix = rule.native_prefix.trim_end_matches(['/', '\\']);
+        Some((format!("{native_prefix}{separator}"), rest.as_str().replace('/', separator)))
+    }
+
+    /// Maps the native path already converted into the neutral format back to the crossplatform prefix, if a rule matches.  \
+    ///
+    /// For TargetOs::Windows the native prefixes are compared case-insensitive.
+    pub(crate) fn map_to_cross(&self, neutral_path: &CrossPath, target_os: TargetOs) -> Option<CrossPathBuf> {
+        let ignore_case = target_os == TargetOs::Windows;
+        let (rule, rest) = longest_match(self.rules.iter().filter(|rule| rule.target_os == target_os).filter_map(|rule| {
+            let rest = crate::strip_folder(neutral_path, &rule.neutral_native_prefix, ignore_case)?;
+            Some((rule, rule.neutral_native_prefix.components().count(), rest))
+        }))?;
+        if rest.as_str().is_empty() {
+            return Some(rule.cross_prefix.clone());
+        }
+        rule.cross_prefix.join_relative(rest.as_str()).ok()
+    }
+}
+
+/// Returns the rule with the longest prefix. On equal length the first rule wins.
+fn longest_match<'a, 'b>(
+    matches: impl Iterator<Item = (&'a MappingRule, usize, &'b CrossPath)>,
+) -> Option<(&'a MappingRule, &'b CrossPath)> {
+    let mut best: Option<(&MappingRule, usize, &CrossPath)> = None;
+    for (rule, prefix_len, rest) in matches {
+        if best.is_none_or(|(_rule, best_len, _rest)| prefix_len > best_len) {
+            best = Some((rule, prefix_len, rest));
+        }
+    }
+    best.map(|(rule, _prefix_len, rest)| (rule, rest))
+}
+
+/// The prefixes must start with an anchor like `/`, `~`, `/mnt/c` or `//server/share`.
+fn is_absolute(cross_path: &CrossPath) -> bool {
+    !matches!(cross_path.components().next(), None | Some(CrossComponent::Normal(_)))
+}
@@ -0,0 +1,54 @@
+// crossplatform_path/src/relative.rs
+
+//! Relative path computation between two neutral crossplatform paths.
+//!
+//! The comparison is component-wise, so `/home/user2` does not start with `/home/user`.
+//! The anchors are compared as whole components: `~/a` is never under `/home/user` and `/tmp/a` is never under `/`.
+//! To compare the paths as they are on a specific machine, first resolve the anchors with CrossPath::expand_anchors_with().
+
+use crate::{CrossComponent, CrossPath};
+
+/// Whether CrossPath::relative_to_with() can climb out of the base with `../`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ParentTraversal {
+    /// The path must be inside the base, so the result never contains `..`. This is the default.
+    #[default]
+    Forbid,
+    /// The result can start with `../` to climb out of the base to the common ancestor.
+    Allow,
+}
+
+/// Returns the path relative to the base as a string or None if it cannot be computed.  \
+///
+/// The paths with different anchors and an absolute path with a relative one have no relative path.
+pub(crate) fn relative_path(path: &CrossPath, base: &CrossPath, parent_traversal: ParentTraversal) -> Option<String> {
+    let path_components: Vec<CrossComponent<'_>> = path.components().collect();
+    let base_components: Vec<CrossComponent<'_>> = base.components().collect();
+    let common = path_components
+        .iter()
+        .zip(&base_components)
+        .take_while(|(path_component, base_component)| path_component == base_component)
+        .count();
+    let is_anchor = |component: &CrossComponent<'_>| !matches!(component, CrossComponent::Normal(_));
+    if common == 0 && (path_components.first().is_some_and(is_anchor) || base_components.first().is_some_and(is_anchor)) {
+        return None;
+    }
+    let climbed = &base_components[common..];
+    if !climbed.is_empty() {
+        // the `..` of the base cannot be undone without knowing the names above it
+        if parent_traversal == ParentTraversal::Forbid || climbed.contains(&CrossComponent::Normal("..")) {
+            return None;
+        }
+    }
+    let mut names: Vec<&str> = vec![".."; climbed.len()];
+    names.extend(path_components[common..].iter().map(|component| match component {
+        CrossComponent::Normal(name) => *name,
+        _ => unreachable!("only the first component can be an anchor"),
+    }));
+    let mut relative = names.join("/");
+    // the trailing slash of a folder is preserved
+    if common < path_components.len() && path.as_str().ends_with('/') {
+        relative.push('/');
+    }
+    Some(relative)
+}
@@ -0,0 +1,197 @@
+// crossplatform_path/src/resolve_context.rs
+
+//! The context used to resolve the neutral crossplatform path into a path for a specific OS.
+//!
+//! The anchors `~`, `~user`, `/tmp`, `/mnt/c` and `//server/share` have a different meaning on every machine.
+//! The context contains the values that replace them, so the conversion is deterministic.
+
+/// The OS for which the neutral crossplatform path is resolved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TargetOs {
+    /// Windows uses drive letters, backslashes and %TEMP%.
+    Windows,
+    /// Linux uses the neutral format almost unchanged.
+    Linux,
+    /// MacOs behaves like Linux.
+    MacOs,
+}
+
+impl TargetOs {
+    /// Returns the OS this code is compiled for.
+    pub fn current() -> Self {
+        if cfg!(windows) {
+            TargetOs::Windows
+        } else if cfg!(target_os = "macos") {
+            TargetOs::MacOs
+        } else {
+            TargetOs::Linux
+        }
+    }
+}
+
+/// How Windows drives are mounted in a Linux-like environment.  \
+///
+/// The WSL and Cygwin styles are always recognized in CrossPathBuf::new() and normalized to the neutral `/mnt/c`.
+/// The MSYS2 style is recognized only if it is in ValidationProfile::drive_mount_styles, because `/c/data` is also a valid Linux path.  \
+/// The style in ResolveContext is used when rendering the path for Linux.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DriveMountStyle {
+    /// WSL mounts drives like `/mnt/c`. This is also the neutral crossplatform format.
+    #[default]
+    Wsl,
+    /// MSYS2 and Git Bash mount drives like `/c`.
+    Msys,
+    /// Cygwin mounts drives like `/cygdrive/c`.
+    Cygwin,
+}
+
+impl DriveMountStyle {
+    /// All the styles, for a ValidationProfile that recognizes paths from tooling logs.
+    pub const ALL: [DriveMountStyle; 3] = [DriveMountStyle::Wsl, DriveMountStyle::Cygwin, DriveMountStyle::Msys];
+
+    /// Returns the folder where the drive letters are mounted.
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            DriveMountStyle::Wsl => "/mnt/",
+            DriveMountStyle::Msys => "/",
+            DriveMountStyle::Cygwin => "/cygdrive/",
+        }
+    }
+
+    /// Recognizes a drive mounted in this style at the start of the path with slashes.  \
+    ///
+    /// Returns the drive letter and the rest of the path after it. The drive letter must be a whole component.
+    pub fn split_drive<'a>(&self, path: &'a str) -> Option<(char, &'a str)> {
+        let rest = path.strip_prefix(self.prefix())?;
+        let letter = rest.chars().next().filter(char::is_ascii_alphabetic)?;
+        let after_letter = &rest[1..];
+        (after_letter.is_empty() || after_letter.starts_with('/')).then_some((letter, after_letter))
+    }
+}
+
+/// The length limits of paths and names, checked with CrossPath::check_length_with().  \
+///
+/// The defaults are the Windows MAX_PATH and the 255 limit for names common on Linux filesystems and NTFS.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LengthLimits {
+    /// The Windows MAX_PATH in UTF-16 units, including the terminating NULL. The default is 260.
+    pub max_path_windows: usize,
+    /// The Windows limit for paths with the extended-length prefix `\\?\` in UTF-16 units. The default is 32767.
+    pub max_path_windows_extended: usize,
+    /// The Linux PATH_MAX in bytes, including the terminating NULL. The default is 4096.
+    pub max_path_nix: usize,
+    /// The maximum length of a file or folder name in bytes. The default is 255.
+    pub max_component_bytes: usize,
+    /// The maximum length of a file or folder name in UTF-16 units like on NTFS. The default is 255.
+    pub max_component_utf16: usize,
+}
+
+/// The defaults are the Windows MAX_PATH and the 255 limit for names.
+impl Default for LengthLimits {
+    /// The defaults are the Windows MAX_PATH and the 255 limit for names.
+    fn default() -> Self {
+        LengthLimits {
+            max_path_windows: 260,
+            max_path_windows_extended: 32767,
+            max_path_nix: 4096,
+            max_component_bytes: 255,
+            max_component_utf16: 255,
+        }
+    }
+}
+
+/// The context of the running process, read from the environment once at the first use.
+static PROCESS_CONTEXT: std::sync::OnceLock<ResolveContext> = std::sync::OnceLock::new();
+
+/// The context for resolving `~`, `/tmp` and Windows drives into a path for the target OS.  \
+///
+/// The methods like to_path_buf_nix() use a context built from the environment with ResolveContext::from_env().  \
+/// Construct a custom context to get deterministic results in tests  \
+/// or to compute the path for a different machine than the one running the code:
+///
+/// ```rust
+/// use crossplatform_path::{CrossPathBuf, ResolveContext, TargetOs};
+///
+/// let ctx = ResolveContext {
+///     home_dir: Some("/home/alice".to_string()),
+///     ..ResolveContext::from_env(TargetOs::Linux)
+/// };
+/// let cross_path = CrossPathBuf::new("~/config")?;
+/// assert_eq!(cross_path.to_path_buf_with(&ctx).to_string_lossy(), "/home/alice/config");
+/// # Ok::<(), crossplatform_path::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolveContext {
+    /// The OS for which the path is resolved.
+    pub target_os: TargetOs,
+    /// The home folder that replaces `~`. If None, the `~` stays unchanged.
+    pub home_dir: Option<String>,
+    /// The home folders of the users that replace `~user`. A user that is not in the map stays unchanged.
+    pub user_home_dirs: std::collections::BTreeMap<String, String>,
+    /// The temp folder that replaces `/tmp` on Windows.
+    pub temp_dir: String,
+    /// How Windows drives are mounted on Linux. The default is `/mnt/c` like in WSL.
+    pub drive_mount_style: DriveMountStyle,
+    /// The folder where UNC network shares `//server/share` are mounted on Linux. The default is `/mnt/unc/`,  \
+    /// so a server with a single letter name like `//c/share` cannot overlap with the drive `/mnt/c`.
+    pub unc_mount_prefix: String,
+    /// The length limits for the target OS.
+    pub length_limits: LengthLimits,
+    /// If true, the Windows path longer than MAX_PATH is written with the extended-length prefix `\\?\`. The default is false.
+    pub extended_length_prefix: bool,
+    /// The user-defined prefix mapping like `/data/shared` to `S:\`. It has priority over the built-in anchors.  \
+    /// The default is PathMapping::process_default(), that is empty if PathMapping::set_process_default() was not called at startup.  \
+    /// It is shared, so cloning the context does not copy the table.
+    pub path_mapping: std::sync::Arc<crate::PathMapping>,
+}
+
+impl ResolveContext {
+    /// Creates the context from the environment of the running process.  \
+    ///
+    /// The home folder is from std::env::home_dir() and the temp folder from std::env::temp_dir().  \
+    /// The user_home_dirs contain only the current user from USER or USERNAME, so `~alice` works for alice.  \
+    /// The path_mapping is the process default from PathMapping::set_process_default().  \
+    /// The environment is read only once at the first use. Later changes of the environment variables are not seen.
+    pub fn from_env(target_os: TargetOs) -> Self {
+        ResolveContext {
+            target_os,
+            ..Self::process().clone()
+        }
+    }
+
+    /// Returns the context of the running process for the current OS.  \
+    ///
+    /// It is created once and then only borrowed, because it resolves the native path of every CrossPathBuf.
+    pub(crate) fn process() -> &'static ResolveContext {
+        PROCESS_CONTEXT.get_or_init(|| Self::read_env(TargetOs::current()))
+    }
+
+    /// Reads the home and temp folders and the current user from the environment.
+    fn read_env(target_os: TargetOs) -> Self {
+        let home_dir = std::env::home_dir().map(|home| home.to_string_lossy().to_string());
+        let current_user = std::env::var("USER").or_else(|_err| std::env::var("USERNAME")).ok();
+        let user_home_dirs = match (current_user, &home_dir) {
+            (Some(user), Some(home)) => std::collections::BTreeMap::from([(user, home.clone())]),
+            _ => std::collections::BTreeMap::new(),
+        };
+        ResolveContext {
+            target_os,
+            home_dir,
+            user_home_dirs,
+            temp_dir: std::env::temp_dir().to_string_lossy().to_string(),
+            drive_mount_style: DriveMountStyle::Wsl,
+            unc_mount_prefix: "/mnt/unc/".to_string(),
+            length_limits: LengthLimits::default(),
+            extended_length_prefix: false,
+            path_mapping: crate::PathMapping::process_default(),
+        }
+    }
+}
+
+/// The default context is from the environment for the current OS.
+impl Default for ResolveContext {
+    /// The default context is from the environment for the current OS.
+    fn default() -> Self {
+        Self::from_env(TargetOs::current())
+    }
+}
@@ -0,0 +1,102 @@
+// crossplatform_path/src/sanitize.rs
+
+//! Sanitizer that turns arbitrary text into a valid file or folder name.
+//!
+//! File names are often generated from user titles or external IDs.
+//! Instead of failing in CrossPathBuf::new(), the offending parts are replaced, so the result always passes the validation.
+
+use crate::ValidationProfile;
+
+/// Options for CrossPathBuf::sanitize_component().
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SanitizeOptions {
+    /// The character that replaces the forbidden characters. The default is `_`.  \
+    ///
+    /// If it is not valid in a name for the profile, `_` is used instead.
+    pub replacement: char,
+    /// The maximum length of the name in bytes. The default is 255, the limit of most Linux filesystems.  \
+    ///
+    /// The name is truncated on a char boundary. The result has always at least one character.
+    pub max_bytes: usize,
+    /// The result passes the validation with this profile. The default is ValidationProfile::default() like in new().
+    pub profile: ValidationProfile,
+}
+
+/// The default replaces with `_` and truncates to 255 bytes for the default profile.
+impl Default for SanitizeOptions {
+    /// The default replaces with `_` and truncates to 255 bytes for the default profile.
+    fn default() -> Self {
+        SanitizeOptions {
+            replacement: '_',
+            max_bytes: 255,
+            profile: ValidationProfile::default(),
+        }
+    }
+}
+
+/// Turns arbitrary text into one valid file or folder name.
+pub(crate) fn sanitize_component(text: &str, options: &SanitizeOptions) -> String {
+    let profile = &options.profile;
+    let replacement = if is_valid_replacement(options.replacement, profile) {
+        options.replacement
+    } else {
+        '_'
+    };
+    // new() trims the leading whitespace, so it is removed here.
+    let mut name = String::new();
+    for c in text.trim_start().chars() {
+        let is_control = matches!(c, '\u{0}'..='\u{1F}' | '\u{7F}');
+        if c == '\u{0}' || (is_control && profile.forbid_control_chars) {
+            // control bytes are stripped
+            continue;
+        }
+        if profile.forbid_unsafe_unicode && crate::validation::is_unsafe_unicode(c) {
+            // the invisible characters are stripped too, a visible replacement would spoof the name
+            continue;
+        }
+        // slash and backslash are separators and cannot be a part of a name
+        let is_forbidden = matches!(c, '/' | '\\')
+            || profile.forbidden_chars.contains(&c)
+            || (c == ':' && profile.forbid_colon)
+            || (profile.portable_chars_only && !(c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-')))
+            || (profile.portable_chars_only && c == '-' && name.is_empty());
+        name.push(if is_forbidden { replacement } else { c });
+    }
+    truncate_and_trim(&mut name, options.max_bytes, profile);
+    // the special names cannot be a normal name
+    if name.is_empty() || name == "." || name == ".." {
+        name = replacement.to_string();
+    }
+    // the home anchor `~` or `~user` would not be a normal name in the first component
+    if crate::components::is_home_anchor(&name) {
+        name.replace_range(..1, &replacement.to_string());
+    }
+    // reserved device names get the replacement as prefix, so con.txt becomes _con.txt
+    if profile.forbid_reserved_names && crate::validation::is_reserved_device_name(&name) {
+        name.insert(0, replacement);
+        truncate_and_trim(&mut name, options.max_bytes, profile);
+    }
+    name
+}
+
+/// Truncates the name to max_bytes on a char boundary and removes the trailing spaces and dots if the profile forbids them.
+fn truncate_and_trim(name: &mut String, max_bytes: usize, profile: &ValidationProfile) {
+    if name.len() > max_bytes {
+        let mut end = max_bytes;
+        while !name.is_char_boundary(end) {
+            end -= 1;
+        }
+        name.truncate(end);
+    }
+    if profile.forbid_trailing_space_or_dot {
+        let trimmed_len = name.trim_end_matches([' ', '.']).len();
+        name.truncate(trimmed_len);
+    }
+}
+
+/// The replacement must be a valid character in any position of a name.
+fn is_valid_replacement(replacement: char, profile: &ValidationProfile) -> bool {
+    !matches!(replacement, '/' | '\\' | '.' | ' ' | '-' | '~')
+        && !replacement.is_whitespace()
+        && crate::validation::validate_path(&replacement.to_string(), profile).is_valid()
+}
@@ -37,7 +37,7 @@ fn test_04_invalid_character() {
     match CrossPathBuf::new(r#"c:\test:\path"#) {
         Err(err) => assert_eq!(
             err.to_string(),
-            r#"The string /mnt/c/test:/path contains an invalid windows path character < > : " | ? * "#
+            r#"The string c:\test:\path contains an invalid windows path character < > : " | ? * "#
         ),
         Ok(_path) => (),
     }
@@ -47,8 +47,21 @@ fn test_04_invalid_character() {
 fn test_05_home() {
     let cross_path = CrossPathBuf::new(r#"~/test/path"#).expect("test");
     assert_eq!(cross_path.as_str(), "~/test/path");
-    assert_eq!(cross_path.to_path_buf_nix().to_string_lossy(), "/home/rustdevuser/test/path");
-    assert_eq!(cross_path.to_path_buf_win().to_string_lossy(), "/home/rustdevuser/test/path");
+    let ctx = ResolveContext {
+        home_dir: Some("/home/rustdevuser".to_string()),
+        ..ResolveContext::from_env(TargetOs::Linux)
+    };
+    assert_eq!(cross_path.to_path_buf_with(&ctx).to_string_lossy(), "/home/rustdevuser/test/path");
+    let ctx = ResolveContext {
+        target_os: TargetOs::Windows,
+        ..ctx
+    };
+    assert_eq!(cross_path.to_path_buf_with(&ctx).to_string_lossy(), "/home/rustdevuser/test/path");
+    // without a context the home folder is from the environment
+    assert_eq!(
+        cross_path.to_path_buf_nix(),
+        cross_path.to_path_buf_with(&ResolveContext::from_env(TargetOs::Linux))
+    );
 }
 
 /// test join_relative
@@ -86,3 +99,1567 @@ fn test_07_file_name() {
     assert_eq!(cross_path.file_stem().expect("test"), "bar");
     assert_eq!(cross_path.extension().expect("test"), "md");
 }
+
+#[test]
+fn test_08_cross_path_borrowed() {
+    fn takes_cross_path(cross_path: &CrossPath) -> &str {
+        cross_path.as_str()
+    }
+    let cross_path_buf = CrossPathBuf::new(r#"/mnt/c/foo/bar.txt"#).expect("test");
+    assert_eq!(takes_cross_path(&cross_path_buf), "/mnt/c/foo/bar.txt");
+
+    let parent = cross_path_buf.parent().expect("test");
+    assert_eq!(parent.as_str(), "/mnt/c/foo");
+    assert_eq!(parent.parent().expect("test").as_str(), "/mnt/c");
+    assert_eq!(parent.file_name().expect("test"), "foo");
+    assert_eq!(CrossPathBuf::new("/foo").expect("test").parent().expect("test").as_str(), "/");
+    assert!(CrossPathBuf::new("/").expect("test").parent().is_err());
+
+    let cow: std::borrow::Cow<CrossPath> = parent.into();
+    assert_eq!(cow.into_owned(), CrossPathBuf::new(r#"c:\foo"#).expect("test"));
+
+    let cross_path = CrossPathBuf::new(r#".bashrc"#).expect("test");
+    assert_eq!(cross_path.file_stem().expect("test"), ".bashrc");
+    assert_eq!(cross_path.extension().expect("test"), "");
+}
+
+#[test]
+fn test_09_as_ref_path() {
+    fn takes_as_ref_path(path: impl AsRef<std::path::Path>) -> std::path::PathBuf {
+        path.as_ref().to_path_buf()
+    }
+    let cross_path = CrossPathBuf::new(r#"~/test/path"#).expect("test");
+    assert_eq!(takes_as_ref_path(&cross_path), cross_path.to_path_buf_current_os());
+    assert_eq!(cross_path.as_native_path(), cross_path.to_path_buf_current_os());
+    let os_str: &std::ffi::OsStr = cross_path.as_ref();
+    assert_eq!(os_str, cross_path.to_path_buf_current_os().as_os_str());
+    assert_eq!(std::path::PathBuf::from(cross_path.clone()), cross_path.to_path_buf_current_os());
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+struct TestConfig {
+    data_dir: CrossPathBuf,
+    cache_dir: Option<CrossPathBuf>,
+    log_file: Option<CrossPathBuf>,
+    includes: Vec<CrossPathBuf>,
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_10_serde_json() {
+    let config = TestConfig {
+        data_dir: CrossPathBuf::new(r#"c:\data"#).expect("test"),
+        cache_dir: Some(CrossPathBuf::new("~/.cache/app").expect("test")),
+        log_file: None,
+        includes: vec![
+            CrossPathBuf::new("include/a").expect("test"),
+            CrossPathBuf::new("/tmp/b").expect("test"),
+        ],
+    };
+    let json = serde_json::to_string(&config).expect("test");
+    assert_eq!(
+        json,
+        r#"{"data_dir":"/mnt/c/data","cache_dir":"~/.cache/app","log_file":null,"includes":["include/a","/tmp/b"]}"#
+    );
+    let config_2: TestConfig = serde_json::from_str(&json).expect("test");
+    assert_eq!(config, config_2);
+
+    let err =
+        serde_json::from_str::<TestConfig>(r#"{"data_dir":"data?","cache_dir":null,"log_file":null,"includes":[]}"#).expect_err("test");
+    assert!(
+        err.to_string()
+            .contains("The string data? contains an invalid windows path character")
+    );
+    let err = serde_json::from_str::<TestConfig>(r#"{"data_dir":"data","cache_dir":null,"log_file":null,"includes":["a/con/b"]}"#)
+        .expect_err("test");
+    assert!(err.to_string().contains("The path string a/con/b must not contain reserved words"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_10_serde_toml() {
+    let toml_str = r#"
+data_dir = 'd:\data'
+log_file = "/tmp/app.log"
+includes = ["include/a", 'include\b']
+"#;
+    let config: TestConfig = toml::from_str(toml_str).expect("test");
+    assert_eq!(config.data_dir.as_str(), "/mnt/d/data");
+    assert_eq!(config.cache_dir, None);
+    assert_eq!(config.log_file.as_ref().expect("test").as_str(), "/tmp/app.log");
+    assert_eq!(config.includes[1].as_str(), "include/b");
+
+    let toml_str_2 = toml::to_string(&config).expect("test");
+    let config_2: TestConfig = toml::from_str(&toml_str_2).expect("test");
+    assert_eq!(config, config_2);
+
+    let err = toml::from_str::<TestConfig>("data_dir = 'data.'\nincludes = []").expect_err("test");
+    assert!(err.to_string().contains("The path string data. must not end with space or dot"));
+}
+
+#[test]
+fn test_11_std_traits() {
+    use std::collections::{BTreeSet, HashMap};
+
+    let cross_path: CrossPathBuf = r#"foo\bar"#.parse().expect("test");
+    assert_eq!(cross_path, "foo/bar");
+    assert!(CrossPathBuf::try_from("foo?").is_err());
+    assert_eq!(CrossPathBuf::try_from(String::from("foo")).expect("test"), "foo");
+    assert_eq!(CrossPathBuf::try_from(std::path::Path::new("c:/foo")).expect("test"), "/mnt/c/foo");
+    assert!(CrossPathBuf::try_from(std::path::PathBuf::from("foo/con")).is_err());
+    let as_ref_str: &str = cross_path.as_ref();
+    assert_eq!(as_ref_str, "foo/bar");
+
+    let mut map = HashMap::new();
+    map.insert(cross_path.clone(), 1);
+    assert_eq!(
+        map.get(
+            cross_path
+                .parent()
+                .expect("test")
+                .join_relative("bar")
+                .expect("test")
+                .as_cross_path()
+        ),
+        Some(&1)
+    );
+
+    // component-wise ordering and not raw string ordering, where '-' < '/'
+    let set: BTreeSet<CrossPathBuf> = ["a-b", "a/b", "/zz", "a"]
+        .into_iter()
+        .map(CrossPathBuf::new)
+        .collect::<Result<_>>()
+        .expect("test");
+    let sorted: Vec<&str> = set.iter().map(|cross_path| cross_path.as_str()).collect();
+    assert_eq!(sorted, ["/zz", "a", "a/b", "a-b"]);
+
+    let cross_path: CrossPathBuf = ["/mnt/c", "foo", "/bar/"]
+        .into_iter()
+        .map(CrossPathBuf::new)
+        .collect::<Result<_>>()
+        .expect("test");
+    assert_eq!(cross_path, "/mnt/c/foo/bar/");
+    let mut cross_path = cross_path.trim_end_slash().expect("test").to_cross_path_buf();
+    cross_path.extend([CrossPathBuf::new("baz.txt").expect("test")]);
+    assert_eq!(cross_path, "/mnt/c/foo/bar/baz.txt");
+}
+
+#[test]
+fn test_12_components() {
+    let cross_path = CrossPathBuf::new(r#"c:\foo\bar.txt"#).expect("test");
+    let components: Vec<CrossComponent> = cross_path.components().collect();
+    assert_eq!(
+        components,
+        [
+            CrossComponent::Drive('c'),
+            CrossComponent::Normal("foo"),
+            CrossComponent::Normal("bar.txt")
+        ]
+    );
+    let names: Vec<&str> = cross_path.iter().rev().collect();
+    assert_eq!(names, ["bar.txt", "foo", "/mnt/c"]);
+
+    let cross_path = CrossPathBuf::new("~/foo/").expect("test");
+    assert_eq!(
+        cross_path.components().collect::<Vec<_>>(),
+        [CrossComponent::Home, CrossComponent::Normal("foo")]
+    );
+    let cross_path = CrossPathBuf::new("/tmp/foo").expect("test");
+    assert_eq!(cross_path.components().next(), Some(CrossComponent::Temp));
+    // anchors are only whole components
+    let cross_path = CrossPathBuf::new("/tmpdata/mnt/c").expect("test");
+    assert_eq!(
+        cross_path.components().collect::<Vec<_>>(),
+        [
+            CrossComponent::Root,
+            CrossComponent::Normal("tmpdata"),
+            CrossComponent::Normal("mnt"),
+            CrossComponent::Normal("c")
+        ]
+    );
+    let cross_path = CrossPathBuf::new("~backup/x").expect("test");
+    assert_eq!(cross_path.components().next(), Some(CrossComponent::UserHome("backup")));
+    let cross_path = CrossPathBuf::new("~$report.docx").expect("test");
+    assert_eq!(cross_path.components().next(), Some(CrossComponent::Normal("~$report.docx")));
+
+    let cross_path = CrossPathBuf::new("/mnt/d/foo/bar").expect("test");
+    let ancestors: Vec<&str> = cross_path.ancestors().map(|ancestor| ancestor.as_str()).collect();
+    assert_eq!(ancestors, ["/mnt/d/foo/bar", "/mnt/d/foo", "/mnt/d"]);
+    let cross_path = CrossPathBuf::new("foo/bar").expect("test");
+    let ancestors: Vec<&str> = cross_path.ancestors().map(|ancestor| ancestor.as_str()).collect();
+    assert_eq!(ancestors, ["foo/bar", "foo", ""]);
+
+    assert!(CrossPathBuf::new("~").expect("test").file_name().is_err());
+    assert!(CrossPathBuf::new("/tmp").expect("test").parent().is_err());
+    assert_eq!(CrossPathBuf::new("~/foo").expect("test").parent().expect("test").as_str(), "~");
+}
+
+#[test]
+fn test_13_resolve_context() {
+    let ctx = ResolveContext {
+        target_os: TargetOs::Windows,
+        home_dir: Some(r#"C:\Users\alice"#.to_string()),
+        temp_dir: r#"C:\Users\alice\AppData\Local\Temp"#.to_string(),
+        ..ResolveContext::from_env(TargetOs::Windows)
+    };
+    let cross_path = CrossPathBuf::new("/tmp/foo").expect("test");
+    assert_eq!(
+        cross_path.to_path_buf_with(&ctx).to_string_lossy(),
+        r#"C:\Users\alice\AppData\Local\Temp/foo"#
+    );
+    let cross_path = CrossPathBuf::new("~/foo").expect("test");
+    assert_eq!(cross_path.to_path_buf_with(&ctx).to_string_lossy(), r#"C:\Users\alice/foo"#);
+
+    let ctx = ResolveContext {
+        target_os: TargetOs::Linux,
+        home_dir: None,
+        drive_mount_style: DriveMountStyle::Cygwin,
+        ..ctx
+    };
+    assert_eq!(cross_path.to_path_buf_with(&ctx).to_string_lossy(), "~/foo");
+    let cross_path = CrossPathBuf::new(r#"d:\foo"#).expect("test");
+    assert_eq!(cross_path.to_path_buf_with(&ctx).to_string_lossy(), "/cygdrive/d/foo");
+    let cross_path = CrossPathBuf::new("/mnt/data/foo").expect("test");
+    assert_eq!(cross_path.to_path_buf_with(&ctx).to_string_lossy(), "/mnt/data/foo");
+}
+
+#[test]
+fn test_14_to_string_for() {
+    let ctx = ResolveContext {
+        target_os: TargetOs::Linux,
+        home_dir: Some(r#"C:\Users\alice"#.to_string()),
+        temp_dir: r#"C:\Users\alice\AppData\Local\Temp\"#.to_string(),
+        ..ResolveContext::from_env(TargetOs::Linux)
+    };
+    let cross_path = CrossPathBuf::new(r#"c:\test\path"#).expect("test");
+    assert_eq!(cross_path.to_string_for(TargetOs::Windows, &ctx), r#"C:\test\path"#);
+    assert_eq!(cross_path.to_string_for(TargetOs::Linux, &ctx), "/mnt/c/test/path");
+    let cross_path = CrossPathBuf::new("/mnt/d").expect("test");
+    assert_eq!(cross_path.to_string_for(TargetOs::Windows, &ctx), r#"D:\"#);
+    let cross_path = CrossPathBuf::new("~/app/config/").expect("test");
+    assert_eq!(cross_path.to_string_for(TargetOs::Windows, &ctx), r#"C:\Users\alice\app\config\"#);
+    let cross_path = CrossPathBuf::new("/tmp/app").expect("test");
+    assert_eq!(
+        cross_path.to_string_for(TargetOs::Windows, &ctx),
+        r#"C:\Users\alice\AppData\Local\Temp\app"#
+    );
+    assert_eq!(cross_path.to_string_for(TargetOs::MacOs, &ctx), "/tmp/app");
+    let cross_path = CrossPathBuf::new("test/path").expect("test");
+    assert_eq!(cross_path.to_string_for(TargetOs::Windows, &ctx), r#"test\path"#);
+
+    let ctx = ResolveContext {
+        home_dir: Some("/home/alice/".to_string()),
+        ..ctx
+    };
+    let cross_path = CrossPathBuf::new("~/app").expect("test");
+    assert_eq!(cross_path.to_string_for(TargetOs::Linux, &ctx), "/home/alice/app");
+}
+
+#[test]
+fn test_15_expand_env() {
+    let vars = std::collections::HashMap::from([
+        ("XDG_CONFIG_HOME", "/home/alice/.config"),
+        ("CARGO_HOME", "~/.cargo"),
+        ("APPDATA", r#"C:\Users\alice\AppData\Roaming"#),
+        ("ProgramFiles(x86)", r#"C:\Program Files (x86)"#),
+    ]);
+    let lookup = |name: &str| vars.get(name).map(|value| value.to_string());
+
+    let cross_path = CrossPathBuf::new("$XDG_CONFIG_HOME/app").expect("test");
+    assert_eq!(cross_path.expand_env_with(lookup).expect("test"), "/home/alice/.config/app");
+    let cross_path = CrossPathBuf::new("${CARGO_HOME}/bin").expect("test");
+    assert_eq!(cross_path.expand_env_with(lookup).expect("test"), "~/.cargo/bin");
+    let cross_path = CrossPathBuf::new(r#"%APPDATA%\app"#).expect("test");
+    assert_eq!(
+        cross_path.expand_env_with(lookup).expect("test"),
+        "/mnt/c/Users/alice/AppData/Roaming/app"
+    );
+    let cross_path = CrossPathBuf::new(r#"%ProgramFiles(x86)%\app"#).expect("test");
+    assert_eq!(cross_path.expand_env_with(lookup).expect("test"), "/mnt/c/Program Files (x86)/app");
+    // not variables
+    let cross_path = CrossPathBuf::new("100%/cost_$5/{x}").expect("test");
+    assert_eq!(cross_path.expand_env_with(lookup).expect("test"), "100%/cost_$5/{x}");
+
+    let cross_path = CrossPathBuf::new("$NOT_SET/app").expect("test");
+    match cross_path.expand_env_with(lookup) {
+        Err(Error::EnvVarNotSet(name)) => assert_eq!(name, "NOT_SET"),
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+fn test_16_unc() {
+    let cross_path = CrossPathBuf::new(r#"\\server\share\dir"#).expect("test");
+    assert_eq!(cross_path.as_str(), "//server/share/dir");
+    assert_eq!(
+        cross_path.components().collect::<Vec<_>>(),
+        [
+            CrossComponent::Unc {
+                server: "server",
+                share: "share"
+            },
+            CrossComponent::Normal("dir")
+        ]
+    );
+    assert_eq!(cross_path.parent().expect("test").as_str(), "//server/share");
+    let ctx = ResolveContext {
+        unc_mount_prefix: "/mnt/net/".to_string(),
+        ..ResolveContext::from_env(TargetOs::Linux)
+    };
+    assert_eq!(cross_path.to_string_for(TargetOs::Windows, &ctx), r#"\\server\share\dir"#);
+    assert_eq!(cross_path.to_string_for(TargetOs::Linux, &ctx), "/mnt/net/server/share/dir");
+    assert_eq!(cross_path.to_path_buf_with(&ctx).to_string_lossy(), "/mnt/net/server/share/dir");
+    // the default mount folder does not overlap with the drive /mnt/c
+    let cross_path = CrossPathBuf::new(r#"\\c\share\x"#).expect("test");
+    assert_eq!(cross_path.as_str(), "//c/share/x");
+    let ctx = ResolveContext::from_env(TargetOs::Linux);
+    assert_eq!(cross_path.to_string_for(TargetOs::Linux, &ctx), "/mnt/unc/c/share/x");
+    assert_eq!(cross_path.to_path_buf_with(&ctx).to_string_lossy(), "/mnt/unc/c/share/x");
+
+    // extended-length prefixes
+    let cross_path = CrossPathBuf::new(r#"\\?\C:\long\path"#).expect("test");
+    assert_eq!(cross_path.as_str(), "/mnt/c/long/path");
+    let cross_path = CrossPathBuf::new(r#"\\?\UNC\server\share\dir"#).expect("test");
+    assert_eq!(cross_path.as_str(), "//server/share/dir");
+
+    // server and share are mandatory and double slash is still forbidden inside the path
+    assert!(CrossPathBuf::new(r#"\\server"#).is_err());
+    assert!(CrossPathBuf::new(r#"\\server\\share"#).is_err());
+    assert!(CrossPathBuf::new("//server/share//dir").is_err());
+    assert!(CrossPathBuf::new("foo//bar").is_err());
+}
+
+#[test]
+fn test_17_drive_mount_style() {
+    let neutral = CrossPathBuf::new(r#"d:\x"#).expect("test");
+    assert_eq!(neutral, "/mnt/d/x");
+    assert_eq!(CrossPathBuf::new("/mnt/D/x").expect("test"), neutral);
+    // the Cygwin drive is unambiguous, the MSYS2 drive is opt-in, because /d/x is also a valid Linux path
+    assert_eq!(CrossPathBuf::new("/cygdrive/d/x").expect("test"), neutral);
+    assert_eq!(CrossPathBuf::new("/d/x").expect("test"), "/d/x");
+    let tooling_logs = ValidationProfile {
+        drive_mount_styles: DriveMountStyle::ALL.to_vec(),
+        ..ValidationProfile::default()
+    };
+    assert_eq!(
+        CrossPathBuf::new_with_profile("/cygdrive/d/x", &tooling_logs).expect("test"),
+        neutral
+    );
+    assert_eq!(CrossPathBuf::new_with_profile("/d/x", &tooling_logs).expect("test"), neutral);
+    let msys_only = ValidationProfile {
+        drive_mount_styles: vec![DriveMountStyle::Msys],
+        ..ValidationProfile::default()
+    };
+    assert_eq!(CrossPathBuf::new_with_profile("/d/x", &msys_only).expect("test"), neutral);
+    assert_eq!(CrossPathBuf::new_with_profile("/cygdrive/d/x", &msys_only).expect("test"), neutral);
+    let resolving_tooling_logs = ValidationProfile {
+        dot_components: DotComponents::Resolve,
+        ..tooling_logs.clone()
+    };
+    assert!(matches!(
+        CrossPathBuf::new_with_profile("/cygdrive/d/..", &resolving_tooling_logs),
+        Err(Error::ClimbsAboveStart(_))
+    ));
+    // a Linux path with a single letter folder round-trips unchanged
+    let cross_path = CrossPathBuf::new("/a/b").expect("test");
+    assert_eq!(cross_path, "/a/b");
+    assert_eq!(cross_path.components().next(), Some(CrossComponent::Root));
+    let linux_ctx = ResolveContext::from_env(TargetOs::Linux);
+    assert_eq!(cross_path.to_path_buf_with(&linux_ctx), std::path::PathBuf::from("/a/b"));
+    assert_eq!(
+        CrossPathBuf::from_path(std::path::Path::new("/x/data"))
+            .expect("test")
+            .to_path_buf_with(&linux_ctx),
+        std::path::PathBuf::from("/x/data")
+    );
+    assert_eq!(CrossPathBuf::new("D:x").expect("test"), neutral);
+    assert_eq!(CrossPathBuf::new("z:").expect("test"), "/mnt/z");
+    // not drives
+    assert_eq!(
+        CrossPathBuf::new_with_profile("/cygdrive/data", &tooling_logs).expect("test"),
+        "/cygdrive/data"
+    );
+    assert_eq!(CrossPathBuf::new("/dd/x").expect("test"), "/dd/x");
+    assert!(CrossPathBuf::new("1:/x").is_err());
+
+    let ctx = ResolveContext {
+        drive_mount_style: DriveMountStyle::Msys,
+        ..ResolveContext::from_env(TargetOs::Linux)
+    };
+    assert_eq!(neutral.to_string_for(TargetOs::Linux, &ctx), "/d/x");
+    assert_eq!(neutral.to_path_buf_with(&ctx).to_string_lossy(), "/d/x");
+    let ctx = ResolveContext {
+        drive_mount_style: DriveMountStyle::Cygwin,
+        ..ctx
+    };
+    assert_eq!(neutral.to_string_for(TargetOs::Linux, &ctx), "/cygdrive/d/x");
+    assert_eq!(neutral.to_string_for(TargetOs::Windows, &ctx), r#"D:\x"#);
+}
+
+#[test]
+fn test_18_component_validation() {
+    assert!(matches!(CrossPathBuf::new("foo./bar"), Err(Error::MustNotEndWith(_))));
+    assert!(matches!(CrossPathBuf::new("dir /file"), Err(Error::MustNotEndWith(_))));
+    assert!(matches!(CrossPathBuf::new("file "), Err(Error::MustNotEndWith(_))));
+    assert!(matches!(CrossPathBuf::new("/home/con.txt"), Err(Error::ReservedWords(_))));
+    assert!(matches!(CrossPathBuf::new("nul.tar.gz"), Err(Error::ReservedWords(_))));
+    assert!(matches!(CrossPathBuf::new("COM1 .txt/x"), Err(Error::ReservedWords(_))));
+    assert!(matches!(CrossPathBuf::new("CONIN$"), Err(Error::ReservedWords(_))));
+    assert!(matches!(CrossPathBuf::new("a/COM¹"), Err(Error::ReservedWords(_))));
+    assert!(matches!(CrossPathBuf::new("lpt0"), Err(Error::ReservedWords(_))));
+    assert!(matches!(CrossPathBuf::new("a/./b"), Err(Error::ReservedWords(_))));
+    assert!(matches!(CrossPathBuf::new(".."), Err(Error::ReservedWords(_))));
+
+    assert!(CrossPathBuf::new("/home/console/com10/lpt.txt").is_ok());
+    assert!(CrossPathBuf::new("~/.config/nullable").is_ok());
+}
+
+#[test]
+fn test_19_validation_report() {
+    let report = CrossPathBuf::validate("/home/dir /con.txt/a|b");
+    assert!(!report.is_valid());
+    let rules: Vec<ValidationRule> = report.violations.iter().map(|violation| violation.rule).collect();
+    assert_eq!(
+        rules,
+        [
+            ValidationRule::InvalidCharacter,
+            ValidationRule::MustNotEndWith,
+            ValidationRule::ReservedWord
+        ]
+    );
+    assert_eq!(report.violations[0].byte_offset, 20);
+    assert_eq!(report.violations[0].component_index, 3);
+    assert_eq!(report.violations[0].character, Some('|'));
+    assert_eq!(report.violations[1].byte_offset, 9);
+    assert_eq!(report.violations[1].component_index, 1);
+    assert_eq!(report.violations[1].character, Some(' '));
+    assert_eq!(report.violations[2].byte_offset, 11);
+    assert_eq!(report.violations[2].component_index, 2);
+    assert_eq!(report.violations[2].character, None);
+
+    // offsets are relative to the input, also with leading whitespace and multibyte chars
+    let report = CrossPathBuf::validate("  čšž/a*");
+    assert_eq!(report.violations.len(), 1);
+    assert_eq!(report.violations[0].byte_offset, 10);
+    assert_eq!(report.violations[0].char_offset, 7);
+    assert_eq!(report.violations[0].component_index, 1);
+
+    // the colon is allowed only after the drive letter
+    let report = CrossPathBuf::validate(r#"c:\test:\path"#);
+    assert_eq!(report.violations.len(), 1);
+    assert_eq!(report.violations[0].byte_offset, 7);
+
+    let report = CrossPathBuf::validate("foo//bar\u{7}");
+    let rules: Vec<ValidationRule> = report.violations.iter().map(|violation| violation.rule).collect();
+    assert_eq!(rules, [ValidationRule::ForbiddenAscii, ValidationRule::EmptyComponent]);
+    assert_eq!(report.violations[1].byte_offset, 4);
+
+    assert!(CrossPathBuf::validate(r#"\\?\UNC\server\share\dir"#).is_valid());
+    assert!(CrossPathBuf::validate("~/.config/app").is_valid());
+
+    // Error carries the same structured violation
+    match CrossPathBuf::new("data?") {
+        Err(Error::InvalidCharacter(violation)) => {
+            assert_eq!(violation.byte_offset, 4);
+            assert_eq!(violation.character, Some('?'));
+        }
+        _ => panic!("expected InvalidCharacter"),
+    }
+    match CrossPathBuf::new("foo//bar") {
+        Err(err @ Error::EmptyComponent(_)) => {
+            assert_eq!(
+                err.to_string(),
+                "The path string foo//bar contains an empty name between two slashes at byte 4 "
+            );
+        }
+        other => panic!("expected EmptyComponent {other:?}"),
+    }
+    assert!(matches!(CrossPathBuf::new("//server"), Err(Error::EmptyComponent(_))));
+}
+
+#[test]
+fn test_20_validation_profile() {
+    let linux = ValidationProfile::permissive_linux();
+    let cross_path = CrossPathBuf::new_with_profile("/home/user/what?:now./con", &linux).expect("test");
+    assert_eq!(cross_path.as_str(), "/home/user/what?:now./con");
+    assert!(CrossPathBuf::new("/home/user/what?").is_err());
+    assert!(CrossPathBuf::new_with_profile("a\u{0}b", &linux).is_err());
+    assert!(CrossPathBuf::new_with_profile("a/../b", &linux).is_err());
+    assert!(CrossPathBuf::new_with_profile("a//b", &linux).is_err());
+    // the colon is allowed in linux names, so `c:` is not a Windows drive
+    let cross_path = CrossPathBuf::new_with_profile("a:b", &linux).expect("test");
+    assert_eq!(cross_path.as_str(), "a:b");
+    assert_eq!(cross_path.components().collect::<Vec<_>>(), [CrossComponent::Normal("a:b")]);
+    assert_eq!(CrossPathBuf::new("a:b").expect("test"), "/mnt/a/b");
+
+    let portable = ValidationProfile::posix_portable();
+    assert!(CrossPathBuf::new_with_profile(r#"c:\data\file-1_v2.txt"#, &portable).is_ok());
+    assert!(matches!(
+        CrossPathBuf::new_with_profile("data/my file", &portable),
+        Err(Error::NotPortable(_))
+    ));
+    assert!(matches!(
+        CrossPathBuf::new_with_profile("data/-rf", &portable),
+        Err(Error::NotPortable(_))
+    ));
+    assert!(matches!(
+        CrossPathBuf::new_with_profile("data/čaj", &portable),
+        Err(Error::NotPortable(_))
+    ));
+    assert!(matches!(
+        CrossPathBuf::new_with_profile("data/nul.txt", &portable),
+        Err(Error::ReservedWords(_))
+    ));
+    let report = CrossPathBuf::validate_with_profile("ab/c d", &portable);
+    assert_eq!(report.violations.len(), 1);
+    assert_eq!(report.violations[0].rule, ValidationRule::NotPortable);
+    assert_eq!(report.violations[0].byte_offset, 4);
+
+    let finder = ValidationProfile::macos_finder();
+    assert!(CrossPathBuf::new_with_profile("~/Music/What?.mp3", &finder).is_ok());
+    assert!(CrossPathBuf::new_with_profile("~/Music/a:b", &finder).is_err());
+    assert!(CrossPathBuf::new_with_profile("~/Music/a\u{7}b", &finder).is_err());
+
+    assert_eq!(ValidationProfile::default(), ValidationProfile::windows_compatible());
+}
+
+#[test]
+fn test_21_sanitize_component() {
+    let options = SanitizeOptions::default();
+    assert_eq!(
+        CrossPathBuf::sanitize_component("a<b>c\"d|e?f*g:h", options.clone()),
+        "a_b_c_d_e_f_g_h"
+    );
+    assert_eq!(CrossPathBuf::sanitize_component("tab\there\u{7f}", options.clone()), "tabhere");
+    assert_eq!(CrossPathBuf::sanitize_component("name. . ", options.clone()), "name");
+    assert_eq!(CrossPathBuf::sanitize_component("NUL.tar.gz", options.clone()), "_NUL.tar.gz");
+    assert_eq!(CrossPathBuf::sanitize_component("..", options.clone()), "_");
+    assert_eq!(CrossPathBuf::sanitize_component("", options.clone()), "_");
+    assert_eq!(CrossPathBuf::sanitize_component("~", options.clone()), "_");
+    assert_eq!(CrossPathBuf::sanitize_component("  leading", options.clone()), "leading");
+
+    // truncated on a char boundary, č has 2 bytes
+    let short = SanitizeOptions {
+        max_bytes: 4,
+        ..SanitizeOptions::default()
+    };
+    assert_eq!(CrossPathBuf::sanitize_component("ččč", short.clone()), "čč");
+    assert_eq!(CrossPathBuf::sanitize_component("abc.def", short.clone()), "abc");
+    assert_eq!(CrossPathBuf::sanitize_component("con.txt", short.clone()), "_con");
+
+    // invalid replacement falls back to underscore
+    let star = SanitizeOptions {
+        replacement: '*',
+        ..SanitizeOptions::default()
+    };
+    assert_eq!(CrossPathBuf::sanitize_component("a?b", star), "a_b");
+
+    let portable = SanitizeOptions {
+        profile: ValidationProfile::posix_portable(),
+        ..SanitizeOptions::default()
+    };
+    assert_eq!(CrossPathBuf::sanitize_component("-my čaj.txt", portable.clone()), "_my__aj.txt");
+    let strict = SanitizeOptions {
+        profile: ValidationProfile::strict(),
+        ..SanitizeOptions::default()
+    };
+    assert_eq!(CrossPathBuf::sanitize_component("gpj\u{202E}.exe", strict), "gpj.exe");
+
+    // the result always passes the validation
+    let nasty = [
+        "",
+        " ",
+        ".",
+        "..",
+        "...",
+        "~",
+        "/",
+        "\\",
+        "a/b",
+        "c:",
+        "con",
+        "COM1 .txt",
+        "lpt¹",
+        "CONIN$",
+        "foo. ",
+        "\u{0}",
+        "\u{1}x\u{1f}",
+        "///",
+        "//server/share",
+        "x:y",
+        "-",
+        "č.",
+        "con.",
+        "nul ..",
+        "ā?",
+        "conout$.log",
+        "gpj\u{202E}.exe",
+        "\u{200B}",
+        "a\u{FEFF}b\u{85}",
+    ];
+    let profiles = [
+        ValidationProfile::windows_compatible(),
+        ValidationProfile::strict(),
+        ValidationProfile::posix_portable(),
+        ValidationProfile::permissive_linux(),
+        ValidationProfile::macos_finder(),
+    ];
+    for profile in profiles {
+        for max_bytes in [1, 2, 3, 4, 5, 255] {
+            for text in nasty {
+                let options = SanitizeOptions {
+                    max_bytes,
+                    profile: profile.clone(),
+                    ..SanitizeOptions::default()
+                };
+                let name = CrossPathBuf::sanitize_component(text, options);
+                assert!(name.len() <= max_bytes.max(1), "{text:?} {name:?}");
+                let cross_path = CrossPathBuf::new_with_profile(&name, &profile).expect(&name);
+                assert_eq!(cross_path.components().collect::<Vec<_>>(), [CrossComponent::Normal(&name)]);
+            }
+        }
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn test_22_from_path_escaped() {
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+    let escaped = |native: &[u8]| CrossPathBuf::from_path_escaped(std::path::Path::new(std::ffi::OsStr::from_bytes(native))).expect("test");
+    assert_eq!(escaped(b"/data/report: Q1?.txt").as_str(), "/data/report%3A Q1%3F.txt");
+    assert_eq!(escaped(b"con.txt/nul").as_str(), "%63on.txt/%6Eul");
+    assert_eq!(escaped(b"dir./file ").as_str(), "dir%2E/file%20");
+    assert_eq!(escaped(b"a\\b/100%41/100%").as_str(), "a%5Cb/100%2541/100%");
+    assert_eq!(escaped(b"x/\xff\xfe.bin").as_str(), "x/%FF%FE.bin");
+    assert_eq!(escaped(b"~/c:").as_str(), "%7E/c%3A");
+    assert_eq!(escaped(b"/c/cygdrive").as_str(), "/c/cygdrive");
+    assert_eq!(escaped(b"/cygdrive/d/x").as_str(), "/%63ygdrive/d/x");
+    assert_eq!(escaped(b"/cygdrive/d/x").as_native_path(), std::path::Path::new("/cygdrive/d/x"));
+    assert_eq!(escaped(b"/home/./..").as_str(), "/home/%2E/%2E%2E");
+    assert_eq!(escaped(b"/home/user/").as_str(), "/home/user/");
+
+    // only the escaped paths are decoded
+    let literal = CrossPathBuf::new("downloads/report%20final%2Epdf").expect("test");
+    assert!(!literal.is_escaped());
+    assert_eq!(literal.as_native_path(), std::path::Path::new("downloads/report%20final%2Epdf"));
+    assert_eq!(
+        literal.to_path_buf_nix(),
+        std::path::PathBuf::from("downloads/report%20final%2Epdf")
+    );
+    let admin = CrossPathBuf::new("%ADMIN%/x").expect("test");
+    assert_eq!(admin.as_native_path(), std::path::Path::new("%ADMIN%/x"));
+    assert_eq!(
+        admin.to_string_for(TargetOs::Windows, &ResolveContext::from_env(TargetOs::Windows)),
+        r#"%ADMIN%\x"#
+    );
+    let report = escaped(b"/data/report: Q1?.txt");
+    assert!(report.is_escaped());
+    assert_eq!(report.as_native_path(), std::path::Path::new("/data/report: Q1?.txt"));
+    assert_eq!(
+        report.to_string_for(TargetOs::Linux, &ResolveContext::from_env(TargetOs::Linux)),
+        "/data/report: Q1?.txt"
+    );
+    assert_eq!(
+        report.as_cross_path().to_path_buf_nix(),
+        std::path::PathBuf::from("/data/report%3A Q1%3F.txt")
+    );
+    let folder = CrossPathBuf::new("/tmp/crossplatform_path_test_22").expect("test");
+    let file = CrossPathBuf::from_path_escaped(std::path::Path::new("/tmp/crossplatform_path_test_22/a: b.txt")).expect("test");
+    file.create_dir_all_for_file().expect("test");
+    file.write_str_to_file("test").expect("test");
+    assert!(file.exists());
+    assert!(file.is_file());
+    assert!(folder.is_dir());
+    folder.remove_dir_all().expect("test");
+    // the paths built from an escaped path stay escaped
+    let file = escaped(b"/data/report: Q1/file.txt");
+    let sibling = file.parent().expect("test").join_relative("other.txt").expect("test");
+    assert!(sibling.is_escaped());
+    assert_eq!(sibling.as_native_path(), std::path::Path::new("/data/report: Q1/other.txt"));
+    let renamed = file.replace_extension("md").expect("test");
+    assert_eq!(renamed.as_native_path(), std::path::Path::new("/data/report: Q1/file.md"));
+    assert_eq!(
+        file.parent().expect("test").add_end_slash().expect("test").as_native_path(),
+        std::path::Path::new("/data/report: Q1/")
+    );
+    assert!(file.to_cross_path_buf().is_escaped());
+    let base = CrossPathBuf::new("/data").expect("test");
+    assert_eq!(
+        file.strip_prefix(&base).expect("test").as_native_path(),
+        std::path::Path::new("report: Q1/file.txt")
+    );
+    // the flag is a part of the identity, because the native paths differ
+    let literal_same = CrossPathBuf::new(file.as_str()).expect("test");
+    assert_ne!(literal_same, file);
+    let mut locations = std::collections::HashSet::new();
+    locations.insert(literal_same.clone());
+    locations.insert(file.clone());
+    assert_eq!(locations.len(), 2);
+    assert!(locations.contains(literal_same.as_cross_path()));
+    // in an escaped path %XX is an escape and not a %VAR%
+    let lookup = |name: &str| (name == "HOME_DIR").then(|| "/home/alice".to_string());
+    let with_variable = CrossPathBuf::new_escaped("%HOME_DIR%/a%3A%3Fb").expect("test");
+    let expanded = with_variable.expand_env_with(lookup).expect("test");
+    assert!(expanded.is_escaped());
+    assert_eq!(expanded.as_native_path(), std::path::Path::new("/home/alice/a:?b"));
+    assert!(matches!(
+        CrossPathBuf::new("a%3A%3Fb").expect("test").expand_env_with(lookup),
+        Err(Error::EnvVarNotSet(name)) if name == "3A"
+    ));
+    let stored = CrossPathBuf::new_escaped(report.as_str()).expect("test");
+    assert_eq!(stored.as_native_path(), report.as_native_path());
+    // the separators are never decoded, so a name cannot become a path
+    let separators = CrossPathBuf::new_escaped("a%2Fb/c%5Cd/e%00").expect("test");
+    assert_eq!(separators.to_path_buf_nix(), std::path::PathBuf::from("a%2Fb/c\\d/e%00"));
+    assert_eq!(
+        separators.to_string_for(TargetOs::Windows, &ResolveContext::from_env(TargetOs::Windows)),
+        r#"a%2Fb\c%5Cd\e%00"#
+    );
+
+    // round-trip property: the native path is byte-identical
+    let alphabet: &[u8] = b"aZ09.%- :?*<>|\"\\~\x01\x7f\xc4\x8d\xff\xfeCONUL1FA";
+    let mut seed: u64 = 0x2545_F491_4F6C_DD1D;
+    let mut random = |max: usize| {
+        seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+        ((seed >> 33) as usize) % max
+    };
+    for _ in 0..2000 {
+        let mut native = if random(2) == 0 { b"/".to_vec() } else { vec![] };
+        let names_count = 1 + random(4);
+        for index in 0..names_count {
+            if index > 0 {
+                native.push(b'/');
+            }
+            for _ in 0..1 + random(6) {
+                native.push(alphabet[random(alphabet.len())]);
+            }
+        }
+        let cross_path = escaped(&native);
+        let ctx = ResolveContext {
+            home_dir: Some("/home/rustdevuser".to_string()),
+            ..ResolveContext::from_env(TargetOs::Linux)
+        };
+        assert_eq!(
+            cross_path.to_path_buf_with(&ctx).into_os_string().into_vec(),
+            native,
+            "{cross_path}"
+        );
+    }
+}
+
+#[test]
+fn test_23_length_limits() {
+    let ctx = ResolveContext {
+        home_dir: Some(r#"C:\Users\rustdevuser"#.to_string()),
+        ..ResolveContext::from_env(TargetOs::Windows)
+    };
+    let long_name = "a".repeat(100);
+    let long_path = CrossPathBuf::new(&format!("c:/{long_name}/{long_name}/{long_name}")).expect("test");
+    assert!(CrossPathBuf::new("c:/short").expect("test").check_length_with(&ctx).is_ok());
+    match long_path.check_length_with(&ctx) {
+        Err(Error::TooLong { length, limit, .. }) => {
+            assert_eq!(length, 306);
+            assert_eq!(limit, 260);
+        }
+        _ => panic!("expected TooLong"),
+    }
+    // the same path is fine on Linux
+    let ctx_nix = ResolveContext {
+        target_os: TargetOs::Linux,
+        ..ctx.clone()
+    };
+    assert!(long_path.check_length_with(&ctx_nix).is_ok());
+
+    // the home folder is expanded before the check
+    let home_path = CrossPathBuf::new(&format!("~/{long_name}/{long_name}/{}", "b".repeat(39))).expect("test");
+    assert!(home_path.check_length_with(&ctx).is_err());
+
+    // the name is checked in bytes and in UTF-16 units, č has 2 bytes and 1 UTF-16 unit
+    let name_ctx = ResolveContext {
+        length_limits: LengthLimits {
+            max_component_utf16: 10,
+            ..LengthLimits::default()
+        },
+        ..ctx_nix.clone()
+    };
+    assert!(
+        CrossPathBuf::new(&"č".repeat(128))
+            .expect("test")
+            .check_length_with(&ctx_nix)
+            .is_err()
+    );
+    assert!(
+        CrossPathBuf::new(&"č".repeat(127))
+            .expect("test")
+            .check_length_with(&ctx_nix)
+            .is_ok()
+    );
+    assert!(
+        CrossPathBuf::new(&"č".repeat(11))
+            .expect("test")
+            .check_length_with(&name_ctx)
+            .is_err()
+    );
+
+    // the extended-length prefix
+    let extended_ctx = ResolveContext {
+        extended_length_prefix: true,
+        ..ctx.clone()
+    };
+    assert!(long_path.check_length_with(&extended_ctx).is_ok());
+    let rendered = long_path.to_string_for(TargetOs::Windows, &extended_ctx);
+    assert_eq!(rendered, format!(r#"\\?\C:\{long_name}\{long_name}\{long_name}"#));
+    assert_eq!(
+        long_path.to_path_buf_with(&extended_ctx).to_string_lossy(),
+        format!(r#"\\?\c:\{long_name}\{long_name}\{long_name}"#)
+    );
+    let unc_path = CrossPathBuf::new(&format!("//server/share/{long_name}/{long_name}/{long_name}")).expect("test");
+    assert!(
+        unc_path
+            .to_string_for(TargetOs::Windows, &extended_ctx)
+            .starts_with(r#"\\?\UNC\server\share\"#)
+    );
+    // short and relative paths don't get the prefix
+    assert_eq!(
+        CrossPathBuf::new("c:/short")
+            .expect("test")
+            .to_string_for(TargetOs::Windows, &extended_ctx),
+        r#"C:\short"#
+    );
+    let relative_path = CrossPathBuf::new(&format!("{long_name}/{long_name}/{long_name}")).expect("test");
+    assert!(!relative_path.to_string_for(TargetOs::Windows, &extended_ctx).starts_with(r#"\\?\"#));
+}
+
+#[test]
+fn test_24_unsafe_unicode() {
+    let strict = ValidationProfile::strict();
+    // gpj.exe with RIGHT-TO-LEFT OVERRIDE is shown as exe.jpg
+    let spoofed = "photos/\u{202E}gpj.exe";
+    assert!(CrossPathBuf::new(spoofed).is_ok());
+    match CrossPathBuf::new_with_profile(spoofed, &strict) {
+        Err(Error::UnsafeUnicode(violation)) => {
+            assert_eq!(violation.byte_offset, 7);
+            assert_eq!(violation.component_index, 1);
+            assert_eq!(violation.character, Some('\u{202E}'));
+        }
+        _ => panic!("expected UnsafeUnicode"),
+    }
+    for c in ['\u{200B}', '\u{200D}', '\u{2066}', '\u{FEFF}', '\u{85}', '\u{9F}', '\u{AD}'] {
+        assert!(matches!(
+            CrossPathBuf::new_with_profile(&format!("a{c}b"), &strict),
+            Err(Error::UnsafeUnicode(_))
+        ));
+    }
+    assert!(CrossPathBuf::new_with_profile("café/日本語/emoji😀", &strict).is_ok());
+
+    // decompress_tar_gz rejects the spoofed names before extraction
+    let folder = CrossPathBuf::new("/tmp/crossplatform_path_test_24").expect("test");
+    let tar_gz_path = folder.join_relative("spoofed.tar.gz").expect("test");
+    tar_gz_path.create_dir_all_for_file().expect("test");
+    let tar_gz = std::fs::File::create(&tar_gz_path).expect("test");
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(tar_gz, flate2::Compression::default()));
+    for name in ["readme.txt", "\u{202E}gpj.exe"] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, name, &b"test"[..]).expect("test");
+    }
+    builder.into_inner().expect("test").finish().expect("test");
+    let destination = folder.join_relative("extracted").expect("test");
+    assert!(matches!(tar_gz_path.decompress_tar_gz(&destination), Err(Error::UnsafeUnicode(_))));
+    assert!(!destination.join_relative("readme.txt").expect("test").exists());
+    folder.remove_dir_all().expect("test");
+}
+
+#[test]
+fn test_25_unicode_normalization() {
+    let nfc = "/home/user/caf\u{E9}";
+    let nfd = "/home/user/cafe\u{301}";
+    let cross_path_nfc = CrossPathBuf::new(nfc).expect("test");
+    let cross_path_nfd = CrossPathBuf::new(nfd).expect("test");
+    assert_ne!(cross_path_nfc, cross_path_nfd);
+    assert!(cross_path_nfc.eq_normalized(&cross_path_nfd));
+    assert!(!cross_path_nfc.eq_normalized(&CrossPathBuf::new("/home/user/cafe").expect("test")));
+
+    let profile = ValidationProfile {
+        normalize_nfc: true,
+        ..ValidationProfile::default()
+    };
+    let normalized = CrossPathBuf::new_with_profile(nfd, &profile).expect("test");
+    assert_eq!(normalized.as_str(), nfc);
+    assert_eq!(normalized, cross_path_nfc);
+
+    let folder = CrossPathBuf::new("/tmp/crossplatform_path_test_25").expect("test");
+    folder.remove_dir_all().expect("test");
+    folder
+        .join_relative("caf\u{E9}")
+        .expect("test")
+        .write_str_to_file("nfc")
+        .expect("test");
+    folder
+        .join_relative("other")
+        .expect("test")
+        .write_str_to_file("other")
+        .expect("test");
+    assert!(folder.normalization_collisions().expect("test").is_empty());
+    folder
+        .join_relative("cafe\u{301}")
+        .expect("test")
+        .write_str_to_file("nfd")
+        .expect("test");
+    let collisions = folder.normalization_collisions().expect("test");
+    // some filesystems like APFS don't allow two names that differ only by normalization
+    if folder.join_relative("cafe\u{301}").expect("test").read_to_string().expect("test") == "nfd"
+        && folder.join_relative("caf\u{E9}").expect("test").read_to_string().expect("test") == "nfc"
+    {
+        assert_eq!(collisions, [vec!["cafe\u{301}".to_string(), "caf\u{E9}".to_string()]]);
+    }
+    folder.remove_dir_all().expect("test");
+}
+
+#[test]
+fn test_26_case_insensitive() {
+    let upper = CrossPathBuf::new(r#"C:\Users\Foo"#).expect("test");
+    let lower = CrossPathBuf::new("/mnt/c/users/foo").expect("test");
+    assert_ne!(upper, lower);
+    assert!(upper.eq_ignore_case(&lower));
+    assert!(
+        CrossPathBuf::new("Straße")
+            .expect("test")
+            .eq_ignore_case(&CrossPathBuf::new("STRASSE").expect("test"))
+    );
+    assert!(
+        CrossPathBuf::new("ΣΊΣΥΦΟΣ")
+            .expect("test")
+            .eq_ignore_case(&CrossPathBuf::new("σίσυφος").expect("test"))
+    );
+    assert!(!upper.eq_ignore_case(&CrossPathBuf::new("/mnt/c/users/bar").expect("test")));
+
+    use std::collections::{BTreeSet, HashSet};
+    let hash_set: HashSet<CaseInsensitive<CrossPathBuf>> = [CaseInsensitive(upper.clone()), CaseInsensitive(lower.clone())].into();
+    assert_eq!(hash_set.len(), 1);
+    let btree_set: BTreeSet<CaseInsensitive<&CrossPath>> = ["B/x", "a/y", "A/Y", "b"]
+        .iter()
+        .map(|str_path| CaseInsensitive(CrossPath::from_valid_str(str_path)))
+        .collect();
+    let sorted: Vec<String> = btree_set.iter().map(|key| key.0.as_str().to_lowercase()).collect();
+    assert_eq!(sorted, ["a/y", "b", "b/x"]);
+
+    // the same file check ignores the case only on Windows drives
+    let ignore_case = CaseSensitivity::IgnoreCaseOnDrives;
+    assert!(upper.is_same_location(&lower, ignore_case));
+    assert!(!upper.is_same_location(&lower, CaseSensitivity::Sensitive));
+    let nix_upper = CrossPathBuf::new("/home/Foo").expect("test");
+    let nix_lower = CrossPathBuf::new("/home/foo").expect("test");
+    assert!(!nix_upper.is_same_location(&nix_lower, ignore_case));
+}
+
+#[test]
+fn test_27_normalize_lexically() {
+    // the default stays strict
+    assert!(CrossPathBuf::new("./data").is_err());
+    assert!(CrossPathBuf::new("a/../b").is_err());
+
+    assert_eq!(CrossPathBuf::normalize_lexically("./data").expect("test"), "data");
+    assert_eq!(CrossPathBuf::normalize_lexically(r#"a\.\b\..\c\"#).expect("test"), "a/c/");
+    assert_eq!(
+        CrossPathBuf::normalize_lexically("/home/user/../other").expect("test"),
+        "/home/other"
+    );
+    assert_eq!(CrossPathBuf::normalize_lexically("~/a/b/../..").expect("test"), "~");
+    assert_eq!(
+        CrossPathBuf::normalize_lexically(r#"c:\Users\..\data"#).expect("test"),
+        "/mnt/c/data"
+    );
+    assert_eq!(CrossPathBuf::normalize_lexically("c:foo/..").expect("test"), "/mnt/c");
+    assert_eq!(
+        CrossPathBuf::normalize_lexically("//server/share/a/../b").expect("test"),
+        "//server/share/b"
+    );
+
+    // climbing above the start or the anchor
+    for str_path in [
+        "../shared/assets",
+        "a/../..",
+        "/..",
+        "~/../etc",
+        "/mnt/c/..",
+        "c:/..",
+        "/tmp/a/../..",
+        "//server/share/..",
+        "/cygdrive/d/..",
+        // the resolution must not create an anchor that was not in the path
+        "a/../~/.ssh",
+        "a/../~root/x",
+        "/x/../tmp/a",
+        "/x/../mnt/c/windows",
+        "a/b/../../~",
+    ] {
+        assert!(
+            matches!(CrossPathBuf::normalize_lexically(str_path), Err(Error::ClimbsAboveStart(_))),
+            "{str_path}"
+        );
+    }
+    let report = CrossPathBuf::validate_with_profile(
+        "~/a/../../b",
+        &ValidationProfile {
+            dot_components: DotComponents::Resolve,
+            ..ValidationProfile::default()
+        },
+    );
+    assert_eq!(report.violations.len(), 1);
+    assert_eq!(report.violations[0].rule, ValidationRule::ClimbsAboveStart);
+    assert_eq!(report.violations[0].byte_offset, 7);
+    assert_eq!(report.violations[0].component_index, 3);
+
+    // the leading .. of a relative path can be kept
+    let keep_leading = ValidationProfile {
+        dot_components: DotComponents::ResolveKeepLeading,
+        ..ValidationProfile::default()
+    };
+    assert_eq!(
+        CrossPathBuf::new_with_profile("../shared/./assets", &keep_leading).expect("test"),
+        "../shared/assets"
+    );
+    assert_eq!(
+        CrossPathBuf::new_with_profile("a/../../../b", &keep_leading).expect("test"),
+        "../../b"
+    );
+    assert!(CrossPathBuf::new_with_profile("/home/../..", &keep_leading).is_err());
+
+    // Extend resolves .. and never climbs above the start of the path
+    let dotted = |str_path: &str| CrossPathBuf::new_with_profile(str_path, &keep_leading).expect("test");
+    let mut cross_path = CrossPathBuf::new("a").expect("test");
+    cross_path.extend([dotted("../../etc")]);
+    assert_eq!(cross_path, "etc");
+    let mut cross_path = CrossPathBuf::new("/home/user").expect("test");
+    cross_path.extend([dotted("../other/"), dotted("../../../etc")]);
+    assert_eq!(cross_path, "/etc");
+    let mut cross_path = CrossPathBuf::new("~/a").expect("test");
+    cross_path.extend([dotted("../../../b")]);
+    assert_eq!(cross_path, "~/b");
+    let mut cross_path = dotted("../shared");
+    cross_path.extend([dotted("../../x")]);
+    assert_eq!(cross_path, "../x");
+    let collected: CrossPathBuf = [dotted("/mnt/c/data"), dotted("../..")].into_iter().collect();
+    assert_eq!(collected, "/mnt/c");
+    // Extend does not create a new anchor either
+    for (base, other, expected) in [
+        ("a", "../~/x", "a/~/x"),
+        ("a", "../~root/x", "a/~root/x"),
+        ("/x", "../tmp/a", "/x/tmp/a"),
+        ("/x", "../mnt/c", "/x/mnt/c"),
+        ("/x", "../y", "/y"),
+    ] {
+        let mut cross_path = dotted(base);
+        cross_path.extend([dotted(other)]);
+        assert_eq!(cross_path, expected, "{base} {other}");
+    }
+    // a name that is not an anchor can still be the first name
+    assert_eq!(CrossPathBuf::normalize_lexically("a/../tmp/x").expect("test"), "tmp/x");
+    assert_eq!(CrossPathBuf::normalize_lexically("/x/../mnt/data").expect("test"), "/mnt/data");
+    let opt_in = ValidationProfile {
+        dot_components: DotComponents::Resolve,
+        drive_mount_styles: DriveMountStyle::ALL.to_vec(),
+        ..ValidationProfile::default()
+    };
+    assert!(matches!(
+        CrossPathBuf::new_with_profile("/x/../cygdrive/c/y", &opt_in),
+        Err(Error::ClimbsAboveStart(_))
+    ));
+}
+
+#[test]
+fn test_28_cross_root() {
+    let folder = CrossPathBuf::new("/tmp/crossplatform_path_test_28").expect("test");
+    if folder.exists() {
+        folder.remove_dir_all().expect("test");
+    }
+    let root = CrossRoot::new(folder.join_relative("tenant_1").expect("test"));
+    assert_eq!(
+        root.join("data/./config.toml").expect("test"),
+        "/tmp/crossplatform_path_test_28/tenant_1/data/config.toml"
+    );
+    assert_eq!(root.join("a/b/../c").expect("test"), "/tmp/crossplatform_path_test_28/tenant_1/a/c");
+    for str_path in [
+        "../tenant_2/config.toml",
+        "a/../../x",
+        "..",
+        "/etc/passwd",
+        "~/.ssh",
+        "c:/windows",
+        "/tmp/x",
+    ] {
+        assert!(matches!(root.join(str_path), Err(Error::EscapesRoot { .. })), "{str_path}");
+    }
+    // the escapes are literal names in a root from new()
+    assert_eq!(
+        root.join("%2E%2E/x").expect("test").as_native_path(),
+        folder.join_relative("tenant_1/%2E%2E/x").expect("test").as_native_path()
+    );
+    // the native path of an escaped root is checked after decoding
+    let escaped_root =
+        CrossRoot::new(CrossPathBuf::from_path_escaped(folder.join_relative("tenant_1").expect("test").as_native_path()).expect("test"));
+    assert!(matches!(
+        escaped_root.read_to_string("%2E%2E/%2E%2E/%2E%2E/etc/hostname"),
+        Err(Error::EscapesRoot { .. })
+    ));
+    assert!(matches!(escaped_root.join("a/%2E%2E/%2E%2E/x"), Err(Error::EscapesRoot { .. })));
+    let slash_name = escaped_root.join("a%2Fb").expect("test");
+    assert_eq!(slash_name.as_native_path().file_name(), Some(std::ffi::OsStr::new("a%2Fb")));
+
+    root.create_dir_all("data").expect("test");
+    root.write_str_to_file("data/config.toml", "test").expect("test");
+    assert_eq!(root.read_to_string("data/config.toml").expect("test"), "test");
+    root.copy_file_to_file("data/config.toml", "data/copy.toml").expect("test");
+    root.rename_or_move("data/copy.toml", "data/moved.toml").expect("test");
+    root.remove_file("data/moved.toml").expect("test");
+    assert!(matches!(root.remove_dir_all(""), Err(Error::EscapesRoot { .. })));
+    assert!(matches!(root.remove_dir_all("data/.."), Err(Error::EscapesRoot { .. })));
+    assert!(matches!(
+        root.write_str_to_file("../tenant_2/config.toml", "test"),
+        Err(Error::EscapesRoot { .. })
+    ));
+
+    // a symlink inside the tree that points outside of the root
+    #[cfg(unix)]
+    {
+        folder.join_relative("tenant_2").expect("test").create_dir_all().expect("test");
+        std::os::unix::fs::symlink(
+            folder.join_relative("tenant_2").expect("test").to_path_buf_current_os(),
+            root.join("link").expect("test").to_path_buf_current_os(),
+        )
+        .expect("test");
+        // the lexical check alone does not see the symlink
+        assert!(root.join("link/config.toml").is_ok());
+        let resolving_root = CrossRoot::new_resolving_symlinks(root.as_cross_path().to_owned());
+        assert!(matches!(resolving_root.join("link/config.toml"), Err(Error::EscapesRoot { .. })));
+        assert!(resolving_root.join("data/config.toml").is_ok());
+        assert!(resolving_root.join("data/new_folder/new.toml").is_ok());
+    }
+    folder.remove_dir_all().expect("test");
+}
+
+#[test]
+fn test_29_relative_paths() {
+    let path = |str_path: &str| CrossPathBuf::new(str_path).expect("test");
+    let cross_path = path("/home/user/project/src/lib.rs");
+
+    // component-wise, not string prefix
+    assert!(cross_path.starts_with(&path("/home/user")));
+    assert!(cross_path.starts_with(&path("/home/user/")));
+    assert!(!cross_path.starts_with(&path("/home/use")));
+    assert!(!path("/home/user2").starts_with(&path("/home/user")));
+    assert!(cross_path.ends_with(&path("src/lib.rs")));
+    assert!(!cross_path.ends_with(&path("rc/lib.rs")));
+    assert!(!path("/project/src").ends_with(&path("/src")));
+    assert!(path("/mnt/c/src").ends_with(&path("/mnt/c/src")));
+
+    // the anchors are whole components
+    assert!(!path("~/a").starts_with(&path("/home/user")));
+    assert!(!path("/home/user/a").starts_with(&path("~")));
+    assert!(!path("/tmp/a").starts_with(&path("/")));
+    assert!(!path("/mnt/c/a").starts_with(&path("/mnt")));
+    assert!(path("/mnt/c/a").starts_with(&path("c:/")));
+    assert!(path("//server/share/a").starts_with(&path("//server/share")));
+    assert!(!path("//server/share2/a").starts_with(&path("//server/share")));
+
+    assert_eq!(cross_path.strip_prefix(&path("/home/user")).expect("test"), "project/src/lib.rs");
+    assert_eq!(path("~/project/src/").strip_prefix(&path("~")).expect("test"), "project/src/");
+    assert_eq!(path("/mnt/c/a").strip_prefix(&path("/mnt/c")).expect("test"), "a");
+    assert_eq!(path("/mnt/c").strip_prefix(&path("/mnt/c")).expect("test"), "");
+    assert!(matches!(
+        path("~/a").strip_prefix(&path("/home/user")),
+        Err(Error::NotUnderBase { .. })
+    ));
+
+    // relative_to
+    assert_eq!(cross_path.relative_to(&path("/home/user/project")).expect("test"), "src/lib.rs");
+    assert_eq!(path("data/config/").relative_to(&path("data")).expect("test"), "config/");
+    let assets = path("/mnt/c/project/assets/logo.png");
+    assert!(assets.relative_to(&path("/mnt/c/project/src")).is_err());
+    assert_eq!(
+        assets
+            .relative_to_with(&path("/mnt/c/project/src/bin"), ParentTraversal::Allow)
+            .expect("test"),
+        "../../assets/logo.png"
+    );
+    assert_eq!(
+        path("/mnt/c/project")
+            .relative_to_with(&path("/mnt/c/project/src"), ParentTraversal::Allow)
+            .expect("test"),
+        ".."
+    );
+    assert_eq!(
+        path("a/b").relative_to_with(&path("c"), ParentTraversal::Allow).expect("test"),
+        "../a/b"
+    );
+    for (str_path, base) in [
+        ("/mnt/c/a", "/mnt/d/a"),
+        ("~/a", "/home/user"),
+        ("/data", "data"),
+        ("data", "/data"),
+        ("/tmp/a", "/"),
+    ] {
+        assert!(
+            matches!(
+                path(str_path).relative_to_with(&path(base), ParentTraversal::Allow),
+                Err(Error::NotUnderBase { .. })
+            ),
+            "{str_path} {base}"
+        );
+    }
+    let keep_leading = ValidationProfile {
+        dot_components: DotComponents::ResolveKeepLeading,
+        ..ValidationProfile::default()
+    };
+    let climbing_base = CrossPathBuf::new_with_profile("../shared", &keep_leading).expect("test");
+    assert!(path("a").relative_to_with(&climbing_base, ParentTraversal::Allow).is_err());
+    // the result with ../ is valid with ResolveKeepLeading
+    let relative = assets
+        .relative_to_with(&path("/mnt/c/project/src"), ParentTraversal::Allow)
+        .expect("test");
+    assert_eq!(
+        CrossPathBuf::new_with_profile(relative.as_str(), &keep_leading).expect("test"),
+        relative
+    );
+
+    // common_ancestor
+    let paths = ["/home/user1/a", "/home/user2/b", "/home/user10"].map(path);
+    assert_eq!(CrossPathBuf::common_ancestor(&paths).expect("test"), "/home");
+    assert_eq!(CrossPathBuf::common_ancestor(&[path("/data"), path("/home")]).expect("test"), "/");
+    assert_eq!(CrossPathBuf::common_ancestor(&[path("a/b"), path("c")]).expect("test"), "");
+    assert_eq!(CrossPathBuf::common_ancestor(&[path("~/a/b")]).expect("test"), "~/a/b");
+    assert!(CrossPathBuf::common_ancestor(&[]).is_none());
+    assert!(CrossPathBuf::common_ancestor(&[path("~/a"), path("/home/user/a")]).is_none());
+    assert!(CrossPathBuf::common_ancestor(&[path("/mnt/c/a"), path("/mnt/d/a")]).is_none());
+    assert!(CrossPathBuf::common_ancestor(&[path("data"), path("/data")]).is_none());
+
+    // the context resolves the anchors
+    let ctx = ResolveContext {
+        home_dir: Some("/home/user".to_string()),
+        temp_dir: r#"C:\Users\user\AppData\Local\Temp\"#.to_string(),
+        ..ResolveContext::from_env(TargetOs::Linux)
+    };
+    assert_eq!(path("~/a").expand_anchors_with(&ctx).expect("test"), "/home/user/a");
+    assert!(
+        path("~/a")
+            .expand_anchors_with(&ctx)
+            .expect("test")
+            .starts_with(&path("/home/user"))
+    );
+    assert_eq!(
+        path("/tmp/build").expand_anchors_with(&ctx).expect("test"),
+        "/mnt/c/Users/user/AppData/Local/Temp/build"
+    );
+    assert_eq!(path("/mnt/c/a").expand_anchors_with(&ctx).expect("test"), "/mnt/c/a");
+    let root_home = ResolveContext {
+        home_dir: Some("/".to_string()),
+        ..ctx
+    };
+    assert_eq!(path("~").expand_anchors_with(&root_home).expect("test"), "/");
+    assert_eq!(path("~/data").expand_anchors_with(&root_home).expect("test"), "/data");
+}
+
+#[test]
+fn test_30_from_path_neutralized() {
+    let linux_ctx = ResolveContext {
+        home_dir: Some("/home/alice".to_string()),
+        temp_dir: "/tmp".to_string(),
+        ..ResolveContext::from_env(TargetOs::Linux)
+    };
+    let neutralize =
+        |str_path: &str, ctx: &ResolveContext| CrossPathBuf::from_path_neutralized(std::path::Path::new(str_path), ctx).expect("test");
+    assert_eq!(neutralize("/home/alice/project", &linux_ctx), "~/project");
+    assert_eq!(neutralize("/home/alice/project/", &linux_ctx), "~/project/");
+    assert_eq!(neutralize("/home/alice", &linux_ctx), "~");
+    assert_eq!(neutralize("/home/alice2/project", &linux_ctx), "/home/alice2/project");
+    assert_eq!(neutralize("/home/Alice/project", &linux_ctx), "/home/Alice/project");
+    assert_eq!(neutralize("/tmp/build", &linux_ctx), "/tmp/build");
+    assert_eq!(neutralize("relative/path", &linux_ctx), "relative/path");
+    // round-trip with the forward conversion
+    for str_path in ["/home/alice/project/Cargo.toml", "/home/alice", "/tmp/build/x", "/etc/hosts"] {
+        let cross_path = neutralize(str_path, &linux_ctx);
+        assert_eq!(cross_path.to_path_buf_with(&linux_ctx), std::path::PathBuf::from(str_path));
+        assert_eq!(cross_path.to_string_for(TargetOs::Linux, &linux_ctx), str_path);
+    }
+
+    let windows_ctx = ResolveContext {
+        home_dir: Some(r#"C:\Users\alice"#.to_string()),
+        temp_dir: r#"C:\Users\alice\AppData\Local\Temp\"#.to_string(),
+        ..ResolveContext::from_env(TargetOs::Windows)
+    };
+    assert_eq!(neutralize(r#"C:\Users\alice\project"#, &windows_ctx), "~/project");
+    assert_eq!(neutralize(r#"c:\users\ALICE\project"#, &windows_ctx), "~/project");
+    // the temp folder inside the home folder is the longer match
+    assert_eq!(neutralize(r#"C:\Users\alice\AppData\Local\Temp\x"#, &windows_ctx), "/tmp/x");
+    assert_eq!(neutralize(r#"C:\Users\alice\AppData\Local"#, &windows_ctx), "~/AppData/Local");
+    assert_eq!(neutralize(r#"D:\Users\alice\project"#, &windows_ctx), "/mnt/d/Users/alice/project");
+    for str_path in [r#"C:\Users\alice\project\Cargo.toml"#, r#"C:\Users\alice\AppData\Local\Temp\x"#] {
+        let cross_path = neutralize(str_path, &windows_ctx);
+        assert_eq!(cross_path.to_string_for(TargetOs::Windows, &windows_ctx), str_path);
+    }
+
+    // a folder that is only an anchor is ignored
+    let root_home = ResolveContext {
+        home_dir: Some("/".to_string()),
+        ..linux_ctx.clone()
+    };
+    assert_eq!(neutralize("/etc/hosts", &root_home), "/etc/hosts");
+    let no_home = ResolveContext {
+        home_dir: None,
+        ..linux_ctx
+    };
+    assert_eq!(neutralize("/home/alice/project", &no_home), "/home/alice/project");
+
+    // on Linux and macOS /tmp is not expanded, so a temp folder that is not /tmp stays unchanged
+    for target_os in [TargetOs::Linux, TargetOs::MacOs] {
+        let ctx = ResolveContext {
+            target_os,
+            home_dir: Some("/home/alice".to_string()),
+            temp_dir: "/var/folders/ab/T".to_string(),
+            ..ResolveContext::from_env(target_os)
+        };
+        assert_eq!(neutralize("/var/folders/ab/T/x", &ctx), "/var/folders/ab/T/x");
+        for str_path in ["/var/folders/ab/T/x", "/tmp/x"] {
+            let cross_path = neutralize(str_path, &ctx);
+            assert_eq!(cross_path.to_path_buf_with(&ctx), std::path::PathBuf::from(str_path));
+            assert_eq!(cross_path.to_string_for(target_os, &ctx), str_path);
+        }
+    }
+}
+
+#[test]
+fn test_31_anchor_matching() {
+    let ctx = ResolveContext {
+        home_dir: Some("/home/alice".to_string()),
+        user_home_dirs: std::collections::BTreeMap::from([("bob".to_string(), "/home/bob".to_string())]),
+        temp_dir: r#"C:\Temp"#.to_string(),
+        ..ResolveContext::from_env(TargetOs::Linux)
+    };
+    let win_ctx = ResolveContext {
+        home_dir: Some(r#"C:\Users\alice"#.to_string()),
+        user_home_dirs: std::collections::BTreeMap::from([("bob".to_string(), r#"C:\Users\bob"#.to_string())]),
+        ..ResolveContext {
+            target_os: TargetOs::Windows,
+            ..ctx.clone()
+        }
+    };
+    let path = |str_path: &str| CrossPathBuf::new(str_path).expect("test");
+
+    // only whole leading components are anchors
+    assert_eq!(path("/tmpdata/x").to_string_for(TargetOs::Windows, &win_ctx), r#"\tmpdata\x"#);
+    assert_eq!(path("/tmpdata/x").to_path_buf_with(&win_ctx).to_string_lossy(), "/tmpdata/x");
+    assert_eq!(path("/tmp/x").to_path_buf_with(&win_ctx).to_string_lossy(), r#"C:\Temp/x"#);
+    assert_eq!(path("/tmp").to_path_buf_with(&win_ctx).to_string_lossy(), r#"C:\Temp"#);
+    assert_eq!(path("/mnt/data/x").to_path_buf_with(&win_ctx).to_string_lossy(), "/mnt/data/x");
+    assert_eq!(path("/mnt/d/x").to_path_buf_with(&win_ctx).to_string_lossy(), "d:/x");
+    assert_eq!(path("~/x").to_path_buf_with(&ctx).to_string_lossy(), "/home/alice/x");
+    assert_eq!(path("~$report.docx").to_path_buf_with(&ctx).to_string_lossy(), "~$report.docx");
+    assert_eq!(path("data/~/x").to_path_buf_with(&ctx).to_string_lossy(), "data/~/x");
+
+    // ~user is resolved from the context
+    assert_eq!(path("~bob/x").components().next(), Some(CrossComponent::UserHome("bob")));
+    assert_eq!(path("~bob/x").to_path_buf_with(&ctx).to_string_lossy(), "/home/bob/x");
+    assert_eq!(path("~bob/x").to_path_buf_with(&ctx), std::path::PathBuf::from("/home/bob/x"));
+    assert_eq!(path("~bob/x").to_path_buf_with(&win_ctx).to_string_lossy(), r#"C:\Users\bob/x"#);
+    assert_eq!(path("~bob/x").to_string_for(TargetOs::Windows, &win_ctx), r#"C:\Users\bob\x"#);
+    assert_eq!(path("~bob").to_string_for(TargetOs::Linux, &ctx), "/home/bob");
+    assert_eq!(path("~bob/x").expand_anchors_with(&ctx).expect("test"), "/home/bob/x");
+    // an unknown user stays unchanged or returns a clear error
+    assert_eq!(path("~carol/x").to_path_buf_with(&ctx).to_string_lossy(), "~carol/x");
+    assert_eq!(path("~carol/x").to_string_for(TargetOs::Linux, &ctx), "~carol/x");
+    assert!(matches!(
+        path("~carol/x").expand_anchors_with(&ctx),
+        Err(Error::UnknownUser(user)) if user == "carol"
+    ));
+    // the file operations never use the literal ~user relative to the current directory
+    let unknown = path("~crossplatform_path_no_user/x");
+    assert!(matches!(
+        unknown.write_str_to_file("test"),
+        Err(Error::UnknownUser(user)) if user == "crossplatform_path_no_user"
+    ));
+    assert!(matches!(unknown.read_to_string(), Err(Error::UnknownUser(_))));
+    assert!(matches!(unknown.create_dir_all(), Err(Error::UnknownUser(_))));
+    assert!(matches!(path("x").copy_file_to_file(&unknown), Err(Error::UnknownUser(_))));
+    assert!(!unknown.exists());
+    assert!(!unknown.as_cross_path().is_dir());
+    assert!(!std::path::Path::new("~crossplatform_path_no_user").exists());
+
+    // ~user is an anchor everywhere
+    assert!(matches!(
+        CrossPathBuf::normalize_lexically("~bob/.."),
+        Err(Error::ClimbsAboveStart(_))
+    ));
+    assert!(path("~bob/x").parent().expect("test") == "~bob");
+    assert!(path("~bob").file_name().is_err());
+    assert!(!path("~bob/x").starts_with(&path("~")));
+    assert_eq!(
+        CrossPathBuf::from_path_neutralized(std::path::Path::new("/home/bob/x"), &ctx).expect("test"),
+        "~bob/x"
+    );
+    assert_eq!(
+        CrossPathBuf::from_path_neutralized(std::path::Path::new("/home/alice/x"), &ctx).expect("test"),
+        "~/x"
+    );
+    let root = CrossRoot::new(path("/srv/tenant"));
+    assert!(matches!(root.join("~bob/x"), Err(Error::EscapesRoot { .. })));
+    assert_eq!(CrossPathBuf::sanitize_component("~bob", SanitizeOptions::default()), "_bob");
+    assert_eq!(CrossPathBuf::sanitize_component("~$report", SanitizeOptions::default()), "~$report");
+}
+
+#[test]
+fn test_32_path_mapping() {
+    let config = r#"
+        # workstation shares
+        windows  /data/shared          =>  S:\
+        windows  /data/shared/archive  =>  \\nas\archive
+        windows  /data/shared/archive  =>  T:\archive
+        # the repository mounted in a Docker container
+        linux    ~/repo                =>  /workspace
+        macos    ~/repo                =>  /Volumes/repo
+    "#;
+    let path_mapping = PathMapping::from_config_str(config).expect("test");
+    assert_eq!(path_mapping.rules().len(), 5);
+    assert_eq!(path_mapping.rules()[0].target_os(), TargetOs::Windows);
+    assert_eq!(path_mapping.rules()[0].cross_prefix(), "/data/shared");
+    assert_eq!(path_mapping.rules()[0].native_prefix(), r#"S:\"#);
+
+    let win_ctx = ResolveContext {
+        home_dir: Some(r#"C:\Users\alice"#.to_string()),
+        path_mapping: path_mapping.clone().into(),
+        ..ResolveContext::from_env(TargetOs::Windows)
+    };
+    let linux_ctx = ResolveContext {
+        home_dir: Some("/home/alice".to_string()),
+        path_mapping: path_mapping.into(),
+        ..ResolveContext::from_env(TargetOs::Linux)
+    };
+    let path = |str_path: &str| CrossPathBuf::new(str_path).expect("test");
+
+    // longest prefix wins, on equal length the first rule wins
+    assert_eq!(
+        path("/data/shared/projects/report.txt").to_string_for(TargetOs::Windows, &win_ctx),
+        r#"S:\projects\report.txt"#
+    );
+    assert_eq!(
+        path("/data/shared/archive/2024/").to_string_for(TargetOs::Windows, &win_ctx),
+        r#"\\nas\archive\2024\"#
+    );
+    assert_eq!(path("/data/shared").to_string_for(TargetOs::Windows, &win_ctx), r#"S:\"#);
+    assert_eq!(
+        path("/data/shared/archive").to_path_buf_with(&win_ctx).to_string_lossy(),
+        r#"\\nas\archive"#
+    );
+    // component-aware
+    assert_eq!(
+        path("/data/shared2/x").to_string_for(TargetOs::Windows, &win_ctx),
+        r#"\data\shared2\x"#
+    );
+    assert_eq!(
+        path("/data/shared/archive2").to_string_for(TargetOs::Windows, &win_ctx),
+        r#"S:\archive2"#
+    );
+    // the rules are per target OS and have priority over the anchors
+    assert_eq!(
+        path("~/repo/src/lib.rs").to_string_for(TargetOs::Linux, &linux_ctx),
+        "/workspace/src/lib.rs"
+    );
+    assert_eq!(
+        path("~/repo/src/lib.rs").to_path_buf_with(&linux_ctx),
+        std::path::PathBuf::from("/workspace/src/lib.rs")
+    );
+    assert_eq!(path("~/other").to_string_for(TargetOs::Linux, &linux_ctx), "/home/alice/other");
+    assert_eq!(path("/data/shared/x").to_string_for(TargetOs::Linux, &linux_ctx), "/data/shared/x");
+    assert_eq!(
+        path("~/repo/src").to_string_for(TargetOs::Windows, &win_ctx),
+        r#"C:\Users\alice\repo\src"#
+    );
+    assert_eq!(path("~/repo/src").to_string_for(TargetOs::MacOs, &linux_ctx), "/Volumes/repo/src");
+
+    // the import maps the native prefix back, longest prefix wins
+    let neutralize =
+        |str_path: &str, ctx: &ResolveContext| CrossPathBuf::from_path_neutralized(std::path::Path::new(str_path), ctx).expect("test");
+    assert_eq!(neutralize("/workspace/src/lib.rs", &linux_ctx), "~/repo/src/lib.rs");
+    assert_eq!(neutralize("/workspace", &linux_ctx), "~/repo");
+    assert_eq!(neutralize("/workspace2/x", &linux_ctx), "/workspace2/x");
+    assert_eq!(neutralize("/home/alice/x", &linux_ctx), "~/x");
+    assert_eq!(
+        neutralize(r#"S:\projects\report.txt"#, &win_ctx),
+        "/data/shared/projects/report.txt"
+    );
+    assert_eq!(neutralize(r#"s:\Projects"#, &win_ctx), "/data/shared/Projects");
+    assert_eq!(neutralize(r#"\\nas\archive\2024"#, &win_ctx), "/data/shared/archive/2024");
+    assert_eq!(neutralize(r#"T:\archive\2024"#, &win_ctx), "/data/shared/archive/2024");
+    assert_eq!(neutralize(r#"T:\other"#, &win_ctx), "/mnt/t/other");
+    // round-trip
+    for str_path in [r#"S:\projects\report.txt"#, r#"\\nas\archive\2024"#] {
+        assert_eq!(neutralize(str_path, &win_ctx).to_string_for(TargetOs::Windows, &win_ctx), str_path);
+    }
+
+    // invalid rules
+    for (config, line) in [
+        ("windows /data", "line 1"),
+        ("\n\nbeos /data => /x", "line 3"),
+        ("linux data => /x", "line 1"),
+        ("linux /data => x", "line 1"),
+        ("linux /da?ta => /x", "line 1"),
+    ] {
+        match PathMapping::from_config_str(config) {
+            Err(Error::InvalidMapping(text)) => assert!(text.contains(line), "{text}"),
+            other => panic!("{config} {other:?}"),
+        }
+    }
+    let mut path_mapping = PathMapping::new();
+    assert!(path_mapping.is_empty());
+    assert!(matches!(
+        path_mapping.add_rule(TargetOs::Linux, "relative", "/x"),
+        Err(Error::InvalidMapping(_))
+    ));
+    path_mapping.add_rule(TargetOs::Linux, "/data", "/mnt/nfs/data").expect("test");
+    assert!(!path_mapping.is_empty());
+
+    let folder = CrossPathBuf::new("/tmp/crossplatform_path_test_32").expect("test");
+    let config_file = folder.join_relative("mapping.conf").expect("test");
+    config_file.create_dir_all_for_file().expect("test");
+    config_file.write_str_to_file("linux /data => /mnt/nfs/data\n").expect("test");
+    assert_eq!(PathMapping::from_config_file(&config_file).expect("test"), path_mapping);
+    folder.remove_dir_all().expect("test");
+
+    // the native prefix of the rule is never decoded, only the escapes after it
+    let mut percent_mapping = PathMapping::new();
+    percent_mapping.add_rule(TargetOs::Linux, "/data/pct", "/srv/100%41").expect("test");
+    let percent_ctx = ResolveContext {
+        path_mapping: percent_mapping.into(),
+        ..ResolveContext::from_env(TargetOs::Linux)
+    };
+    let escaped = CrossPathBuf::new_escaped("/data/pct/a%3Ab").expect("test");
+    assert_eq!(escaped.to_path_buf_with(&percent_ctx), std::path::PathBuf::from("/srv/100%41/a:b"));
+    assert_eq!(escaped.to_string_for(TargetOs::Linux, &percent_ctx), "/srv/100%41/a:b");
+    assert_eq!(
+        path("/data/pct/a%3Ab").to_path_buf_with(&percent_ctx),
+        std::path::PathBuf::from("/srv/100%41/a%3Ab")
+    );
+
+    // the process default is set once and then shared, not copied, by every context and native path
+    let process_mapping = PathMapping::process_default();
+    assert!(matches!(
+        PathMapping::set_process_default(PathMapping::new()),
+        Err(Error::ProcessDefaultAlreadySet)
+    ));
+    assert!(std::sync::Arc::ptr_eq(&process_mapping, &PathMapping::process_default()));
+    assert!(std::sync::Arc::ptr_eq(
+        &process_mapping,
+        &ResolveContext::from_env(TargetOs::Windows).path_mapping
+    ));
+    let cross_path = path("~/data/x.txt");
+    let process_ctx = ResolveContext::from_env(TargetOs::current());
+    assert_eq!(cross_path.as_native_path(), cross_path.to_path_buf_with(&process_ctx));
+    assert_eq!(
+        cross_path.as_cross_path().to_path_buf_current_os(),
+        cross_path.to_path_buf_with(&process_ctx)
+    );
+}
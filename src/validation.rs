@@ -0,0 +1,473 @@
+// crossplatform_path/src/validation.rs
+
+//! Validation rules for the path string and for every single file or folder name inside the path.
+//!
+//! Windows forbids some names in every folder, not only as the whole path.
+//! The rules are checked on every component separately, so `con.txt/foo` and `foo./bar` are rejected,
+//! but `console` and `com10` are allowed.
+//! All the violations are collected into a ValidationReport with the exact position, so an editor can underline them.
+//! The ValidationProfile selects which rules are checked. The default profile is compatible with Windows.
+
+use crate::{CrossComponent, DriveMountStyle, Error};
+
+/// The rule that a path string violates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ValidationRule {
+    /// The characters < > : " | ? * are forbidden. The colon is allowed only after a drive letter like `c:`.
+    InvalidCharacter,
+    /// The ASCII control characters 0-31 and 127 are forbidden.
+    ForbiddenAscii,
+    /// A file or folder name must not end with space or dot.
+    MustNotEndWith,
+    /// Reserved device names like con, nul.txt, com1 and the special names . and ..
+    ReservedWord,
+    /// An empty name between two slashes. Only the UNC network path starts with a double slash.
+    EmptyComponent,
+    /// The POSIX portable filename character set is only A-Z a-z 0-9 . _ - and a name must not start with hyphen.
+    NotPortable,
+    /// Bidirectional overrides like U+202E, zero-width and invisible characters and C1 controls U+0080-U+009F.  \
+    ///
+    /// They can spoof the name in file listings, so `gpj.exe` with RIGHT-TO-LEFT OVERRIDE is shown as `exe.jpg`.
+    UnsafeUnicode,
+    /// The `..` climbs above the start of a relative path or above the anchor `/`, `~`, `/mnt/c`, `/tmp` or `//server/share`.
+    ClimbsAboveStart,
+}
+
+/// How the special names `.` and `..` are handled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DotComponents {
+    /// `.` and `..` are forbidden like reserved words. This is the default guard against traversal attacks.
+    #[default]
+    Reject,
+    /// `.` is removed and `..` removes the preceding name. It must not climb above the start of a relative path or above the anchor.
+    Resolve,
+    /// Like Resolve, but the leading `..` of a relative path are kept, so `../shared/assets` is allowed.
+    ResolveKeepLeading,
+}
+
+/// The set of rules used to validate the path string.  \
+///
+/// The default profile is compatible with Windows, so the path can be used on every OS.  \
+/// Tools that run only on Linux can use a more permissive profile,
+/// and tools that need maximum portability can use a stricter one.  \
+/// The special names `.` and `..`, empty components and the NULL byte are forbidden in every profile.  \
+/// Custom profiles can be built by changing the fields of a built-in profile:
+///
+/// ```rust
+/// use crossplatform_path::{CrossPathBuf, ValidationProfile};
+///
+/// let profile = ValidationProfile {
+///     forbidden_chars: vec!['*'],
+///     ..ValidationProfile::permissive_linux()
+/// };
+/// assert!(CrossPathBuf::new_with_profile("what?", &profile).is_ok());
+/// assert!(CrossPathBuf::new_with_profile("all*", &profile).is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationProfile {
+    /// Characters that are forbidden anywhere in the path.
+    pub forbidden_chars: Vec<char>,
+    /// The colon is forbidden, except after the Windows drive letter like `c:`.  \
+    ///
+    /// Only then `c:` is a drive. Without it the colon is a normal character, so `a:b` is a relative Linux name.
+    pub forbid_colon: bool,
+    /// The ASCII control characters 1-31 and 127 are forbidden. The NULL byte is always forbidden.
+    pub forbid_control_chars: bool,
+    /// File and folder names must not end with space or dot.
+    pub forbid_trailing_space_or_dot: bool,
+    /// Reserved Windows device names like con, nul.txt, com1 are forbidden.
+    pub forbid_reserved_names: bool,
+    /// Only the POSIX portable filename character set A-Z a-z 0-9 . _ - is allowed and a name must not start with hyphen.
+    pub portable_chars_only: bool,
+    /// Bidirectional overrides, zero-width and invisible characters and C1 controls are forbidden.
+    pub forbid_unsafe_unicode: bool,
+    /// Not a rule, but the path is normalized to Unicode NFC in CrossPathBuf::new_with_profile() before the validation.
+    pub normalize_nfc: bool,
+    /// How the special names `.` and `..` are handled. The default is to reject them.
+    pub dot_components: DotComponents,
+    /// The additional drive mounts like MSYS2 `/c` that are recognized and converted to `/mnt/c`.  \
+    ///
+    /// The default is empty, because `/c/data` is also a valid Linux path.
+    /// The Windows `c:`, WSL `/mnt/c` and Cygwin `/cygdrive/c` are always recognized.
+    pub drive_mount_styles: Vec<DriveMountStyle>,
+}
+
+impl ValidationProfile {
+    /// The rules of Windows. A path valid with this profile can be used on every OS. This is the default.
+    pub fn windows_compatible() -> Self {
+        ValidationProfile {
+            forbidden_chars: vec!['<', '>', '"', '|', '?', '*'],
+            forbid_colon: true,
+            forbid_control_chars: true,
+            forbid_trailing_space_or_dot: true,
+            forbid_reserved_names: true,
+            portable_chars_only: false,
+            forbid_unsafe_unicode: false,
+            normalize_nfc: false,
+            dot_components: DotComponents::Reject,
+            drive_mount_styles: vec![],
+        }
+    }
+
+    /// The Windows rules and additionally the bidirectional and invisible Unicode characters are forbidden.
+    pub fn strict() -> Self {
+        ValidationProfile {
+            forbid_unsafe_unicode: true,
+            ..Self::windows_compatible()
+        }
+    }
+
+    /// The strict rules and additionally only the POSIX portable filename character set.
+    pub fn posix_portable() -> Self {
+        ValidationProfile {
+            portable_chars_only: true,
+            ..Self::strict()
+        }
+    }
+
+    /// Linux forbids only the NULL byte, so names with `:`, `?` or a trailing dot are allowed.
+    pub fn permissive_linux() -> Self {
+        ValidationProfile {
+            forbidden_chars: vec![],
+            forbid_colon: false,
+            forbid_control_chars: false,
+            forbid_trailing_space_or_dot: false,
+            forbid_reserved_names: false,
+            portable_chars_only: false,
+            forbid_unsafe_unicode: false,
+            normalize_nfc: false,
+            dot_components: DotComponents::Reject,
+            drive_mount_styles: vec![],
+        }
+    }
+
+    /// The macOS Finder shows the colon as slash, so the colon and control characters are forbidden.
+    pub fn macos_finder() -> Self {
+        ValidationProfile {
+            forbid_colon: true,
+            forbid_control_chars: true,
+            ..Self::permissive_linux()
+        }
+    }
+}
+
+/// The default profile is compatible with Windows.
+impl Default for ValidationProfile {
+    /// The default profile is compatible with Windows.
+    fn default() -> Self {
+        Self::windows_compatible()
+    }
+}
+
+/// One violation of a validation rule with the exact position in the input string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// The rule that is violated.
+    pub rule: ValidationRule,
+    /// The whole input string as it was given to the validation.
+    pub path: String,
+    /// The position of the offending character or name in bytes from the start of the input.
+    pub byte_offset: usize,
+    /// The same position counted in chars, for editors that count characters.
+    pub char_offset: usize,
+    /// The index of the file or folder name in the input. The empty parts between slashes are not counted.
+    pub component_index: usize,
+    /// The offending character. It is None for rules that concern the whole name.
+    pub character: Option<char>,
+}
+
+impl Violation {
+    /// Creates the violation and computes the char offset from the byte offset.
+    pub(crate) fn new(rule: ValidationRule, path: &str, byte_offset: usize, component_index: usize, character: Option<char>) -> Self {
+        Violation {
+            rule,
+            path: path.to_string(),
+            byte_offset,
+            char_offset: path[..byte_offset].chars().count(),
+            component_index,
+            character,
+        }
+    }
+}
+
+/// Converts the violation into the Error variant that carries it.
+impl From<Violation> for Error {
+    /// Converts the violation into the Error variant that carries it.
+    fn from(violation: Violation) -> Self {
+        match violation.rule {
+            ValidationRule::InvalidCharacter => Error::InvalidCharacter(violation),
+            ValidationRule::EmptyComponent => Error::EmptyComponent(violation),
+            ValidationRule::ForbiddenAscii => Error::ForbiddenAscii(violation),
+            ValidationRule::MustNotEndWith => Error::MustNotEndWith(violation),
+            ValidationRule::ReservedWord => Error::ReservedWords(violation),
+            ValidationRule::NotPortable => Error::NotPortable(violation),
+            ValidationRule::UnsafeUnicode => Error::UnsafeUnicode(violation),
+            ValidationRule::ClimbsAboveStart => Error::ClimbsAboveStart(violation),
+        }
+    }
+}
+
+/// All the violations found in a path string.  \
+///
+/// Created with CrossPathBuf::validate(). The violations are in the order the rules are checked.  \
+/// CrossPathBuf::new() returns the first violation as the Error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// The whole input string.
+    pub path: String,
+    /// All the violations found.
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    /// Returns true if there is no violation.
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Returns the first violation as the Error, the same as CrossPathBuf::new() does.
+    pub fn into_result(self) -> crate::Result<()> {
+        match self.violations.into_iter().next() {
+            Some(violation) => Err(violation.into()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Checks the rules of the profile on the input string and collects all the violations.
+pub(crate) fn validate_path(str_path: &str, profile: &ValidationProfile) -> ValidationReport {
+    // The extended-length prefix \\?\ and leading whitespace are not a part of the path.
+    // They are always at the start, so the offsets are just shifted.
+    let stripped = crate::strip_extended_length_prefix(str_path);
+    let body = stripped.trim_start();
+    let shift = str_path.len() - body.len();
+    // Separator is always slash. Backslash is replaced. Backslash must never be a part of a name or path component.
+    let body = body.replace('\\', "/");
+    // the colon is a drive separator only if the profile forbids it in the names
+    let is_drive = profile.forbid_colon && body.chars().next().is_some_and(|c| c.is_ascii_alphabetic()) && body[1..].starts_with(':');
+
+    let components: Vec<(usize, &str)> = body
+        .split('/')
+        .scan(0, |start, name| {
+            let component = (*start, name);
+            *start += name.len() + 1;
+            Some(component)
+        })
+        .filter(|(_start, name)| !name.is_empty())
+        .collect();
+    // The index of the component at the offset in the input. The slash before a name belongs to that name.
+    let component_at = |offset: usize| {
+        components
+            .iter()
+            .filter(|(start, name)| shift + start + name.len() < offset)
+            .count()
+    };
+
+    let mut violations = vec![];
+    let mut violation = |rule, offset: usize, character| {
+        violations.push(Violation::new(rule, str_path, offset, component_at(offset), character));
+    };
+
+    // forbidden: < > : " / \\ | ? *  0 (NULL byte)  0-31 (ASCII control characters)
+    // but / and \\ are delimiters and : is allowed after the windows drive letter.
+    for (pos, c) in body.char_indices() {
+        if profile.forbidden_chars.contains(&c) || (c == ':' && profile.forbid_colon && !(is_drive && pos == 1)) {
+            violation(ValidationRule::InvalidCharacter, shift + pos, Some(c));
+        }
+    }
+    // 0 (NULL byte) and  0-31 (ASCII control characters) 127 is DEL
+    // Important: utf8 is always on a byte level compatible with ASCII7, under 127.
+    // The leading whitespace is checked too, only the extended-length prefix is skipped.
+    let prefix_len = str_path.len() - stripped.len();
+    for (pos, c) in str_path.char_indices().skip_while(|(pos, _c)| *pos < prefix_len) {
+        if c == '\u{0}' || (profile.forbid_control_chars && matches!(c, '\u{1}'..='\u{1F}' | '\u{7F}')) {
+            violation(ValidationRule::ForbiddenAscii, pos, Some(c));
+        }
+    }
+    // Invisible characters can spoof the name in file listings.
+    if profile.forbid_unsafe_unicode {
+        for (pos, c) in body.char_indices() {
+            if is_unsafe_unicode(c) {
+                violation(ValidationRule::UnsafeUnicode, shift + pos, Some(c));
+            }
+        }
+    }
+    // Every component is checked separately, because Windows cannot create a folder like `foo.` or `dir `.
+    for (start, name) in &components {
+        if let Some((rule, pos, character)) = check_component(name, profile) {
+            violation(rule, shift + start + pos, character);
+        }
+    }
+    // With the opt-in resolution `..` removes the preceding name, but it must not climb above the start or the anchor.
+    if profile.dot_components != DotComponents::Reject {
+        let anchor_components = count_anchor_components(&body, &components, is_drive, profile);
+        let is_anchored = is_drive || anchor_components > 0 || body.starts_with('/');
+        // the normal names that `..` can remove
+        let mut names: Vec<(usize, &str)> = vec![];
+        let mut kept_leading = false;
+        for (index, (start, name)) in components.iter().enumerate().skip(anchor_components) {
+            // the drive relative c:foo is the same as c:/foo
+            let (start, name) = if is_drive && index == 0 {
+                (start + 2, &name[2..])
+            } else {
+                (*start, *name)
+            };
+            match name {
+                "." => (),
+                ".." if !names.is_empty() => {
+                    names.pop();
+                }
+                ".." if !is_anchored && profile.dot_components == DotComponents::ResolveKeepLeading => kept_leading = true,
+                ".." => violation(ValidationRule::ClimbsAboveStart, shift + start, None),
+                _ => names.push((start, name)),
+            }
+        }
+        // `a/../~/.ssh` would become the anchor `~` and `/x/../tmp` the anchor `/tmp`, that were not in the path
+        if anchor_components == 0
+            && !is_drive
+            && !kept_leading
+            && let Some((first_start, _first_name)) = names.first()
+        {
+            let root = if body.starts_with('/') { "/" } else { "" };
+            let resolved = format!("{root}{}", names.iter().map(|(_start, name)| *name).collect::<Vec<_>>().join("/"));
+            if starts_with_anchor(&resolved) || split_mounted_drive(&resolved, profile).is_some() {
+                violation(ValidationRule::ClimbsAboveStart, shift + first_start, None);
+            }
+        }
+    }
+    // The POSIX portable filename character set. The drive letter with colon is allowed.
+    if profile.portable_chars_only {
+        for (start, name) in &components {
+            for (pos, c) in name.char_indices() {
+                let is_drive_colon = is_drive && start + pos == 1;
+                if !(c.is_ascii_alphanumeric() || matches!(c, '.' | '_') || (c == '-' && pos > 0) || is_drive_colon) {
+                    violation(ValidationRule::NotPortable, shift + start + pos, Some(c));
+                }
+            }
+        }
+    }
+    // UNC network path //server/share must have both server and share.
+    // Any other double slash is an empty component.
+    let mut after_unc = 0;
+    if let Some(unc_path) = body.strip_prefix("//") {
+        let server_len = unc_path.find('/').unwrap_or(unc_path.len());
+        let share = unc_path.get(server_len + 1..).unwrap_or("");
+        let share_len = share.find('/').unwrap_or(share.len());
+        if server_len == 0 {
+            violation(ValidationRule::EmptyComponent, shift + 2, None);
+        } else if share_len == 0 {
+            violation(ValidationRule::EmptyComponent, shift + (2 + server_len + 1).min(body.len()), None);
+        }
+        after_unc = (2 + server_len).min(body.len());
+    }
+    for (pos, _match) in body[after_unc..].match_indices("//") {
+        violation(ValidationRule::EmptyComponent, shift + after_unc + pos + 1, None);
+    }
+
+    ValidationReport {
+        path: str_path.to_string(),
+        violations,
+    }
+}
+
+/// Counts the leading components of the input that form the anchor, before the drive conversion.  \
+///
+/// `..` must not remove the anchor like `~`, `~user`, `c:`, `/mnt/c`, `/tmp` or `//server/share`
+/// and the opt-in drive mounts `/c` or `/cygdrive/c`.
+fn count_anchor_components(body: &str, components: &[(usize, &str)], is_drive: bool, profile: &ValidationProfile) -> usize {
+    let first = components.first().map_or("", |(_start, name)| *name);
+    if body.starts_with("//") {
+        return 2;
+    }
+    if (is_drive && first.len() == 2) || (crate::components::is_home_anchor(first) && !body.starts_with('/')) {
+        return 1;
+    }
+    if let Some((_letter, rest)) = split_mounted_drive(body, profile) {
+        let anchor_len = body.len() - rest.len();
+        return components.iter().filter(|(start, _name)| *start < anchor_len).count();
+    }
+    if body == "/tmp" || body.starts_with("/tmp/") {
+        return 1;
+    }
+    0
+}
+
+/// Returns `true` if the path starts with an anchor like `~`, `~user`, `/tmp`, `/mnt/c` or `//server/share`. The root `/` is not counted.
+pub(crate) fn starts_with_anchor(path: &str) -> bool {
+    !matches!(
+        crate::CrossPath::from_valid_str(path).components().next(),
+        None | Some(CrossComponent::Root) | Some(CrossComponent::Normal(_))
+    )
+}
+
+/// Recognizes the WSL drive `/mnt/c`, the Cygwin drive `/cygdrive/c` and the drive mounts selected in the profile.  \
+///
+/// Returns the drive letter and the rest of the path after it.
+pub(crate) fn split_mounted_drive<'a>(path: &'a str, profile: &ValidationProfile) -> Option<(char, &'a str)> {
+    [DriveMountStyle::Wsl, DriveMountStyle::Cygwin]
+        .iter()
+        .chain(&profile.drive_mount_styles)
+        .find_map(|style| style.split_drive(path))
+}
+
+/// Checks one file or folder name.  \
+///
+/// Returns the rule, the position inside the name and the offending character.
+fn check_component(component: &str, profile: &ValidationProfile) -> Option<(ValidationRule, usize, Option<char>)> {
+    // . and .. are special names, they are not allowed in the crossplatform path, except with the opt-in resolution
+    if component == "." || component == ".." {
+        return match profile.dot_components {
+            DotComponents::Reject => Some((ValidationRule::ReservedWord, 0, None)),
+            DotComponents::Resolve | DotComponents::ResolveKeepLeading => None,
+        };
+    }
+    // Windows cannot create a file or folder that ends in a space or dot.
+    if profile.forbid_trailing_space_or_dot
+        && let Some(last) = component.chars().last()
+        && (last == ' ' || last == '.')
+    {
+        return Some((ValidationRule::MustNotEndWith, component.len() - 1, Some(last)));
+    }
+    if profile.forbid_reserved_names && is_reserved_device_name(component) {
+        return Some((ValidationRule::ReservedWord, 0, None));
+    }
+    None
+}
+
+/// Windows reserves the device names even with an extension: `con.txt`, `nul.tar.gz`, `COM1 .txt`.  \
+///
+/// The name before the first dot is compared case-insensitive. Trailing spaces are ignored like Windows does.
+pub(crate) fn is_reserved_device_name(component: &str) -> bool {
+    let base = component
+        .split('.')
+        .next()
+        .unwrap_or(component)
+        .trim_end_matches(' ')
+        .to_lowercase();
+    match base.as_str() {
+        "con" | "prn" | "aux" | "nul" | "conin$" | "conout$" => true,
+        _ => {
+            if let Some(number) = base.strip_prefix("com").or_else(|| base.strip_prefix("lpt")) {
+                let mut chars = number.chars();
+                matches!((chars.next(), chars.next()), (Some('0'..='9' | '¹' | '²' | '³'), None))
+            } else {
+                false
+            }
+        }
+    }
+}
+
+/// Bidirectional controls, zero-width and invisible format characters and C1 controls.
+pub(crate) fn is_unsafe_unicode(c: char) -> bool {
+    matches!(c,
+        // C1 control characters
+        '\u{80}'..='\u{9F}'
+        // bidirectional marks, embeddings, overrides and isolates
+        | '\u{61C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+        // zero-width space, joiners, word joiner and invisible operators
+        | '\u{200B}'..='\u{200D}' | '\u{2060}'..='\u{2064}'
+        // deprecated format characters, byte order mark and interlinear annotations
+        | '\u{206A}'..='\u{206F}' | '\u{FEFF}' | '\u{FFF9}'..='\u{FFFB}'
+        // soft hyphen, combining grapheme joiner, Mongolian vowel separator and Hangul fillers
+        | '\u{AD}' | '\u{34F}' | '\u{180E}' | '\u{115F}' | '\u{1160}' | '\u{3164}' | '\u{FFA0}')
+}